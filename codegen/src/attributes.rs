@@ -0,0 +1,285 @@
+use proc_macro2::TokenStream;
+use syn::parse::Parse;
+use syn::{
+    spanned::Spanned, Attribute, Field, Ident, Index, Item, Lit, Meta, NestedMeta, Path, Type,
+};
+
+/// The name of the helper attribute, which is used to configure the generated code.
+/// E.g. `#[merge(track_changes)]`.
+const HELPER_ATTRIBUTE: &str = "merge";
+
+/// The names of all macros of this crate, which can be put onto a src struct.
+/// `struct_merge_partial` is put onto a target struct, which may be a src struct as well.
+const MACRO_NAMES: &[&str] = &[
+    "struct_merge",
+    "struct_merge_ref",
+    "struct_merge_take",
+    "struct_merge3",
+    "try_struct_merge",
+    "struct_merge_partial",
+];
+
+/// Options that are set via `#[merge(...)]` attributes on the src struct.
+#[derive(Clone, Default)]
+pub struct StructOptions {
+    /// Record all fields, whose values actually changed, in the `merge_*report` functions.
+    /// All merged fields must implement `PartialEq`.
+    pub track_changes: bool,
+    /// Generate the `StructMergeIntoPreview` trait in borrowed mode.
+    /// All merged fields must implement `PartialEq` and `Debug`.
+    pub preview: bool,
+    /// Generate the `StructExtractFrom` trait in borrowed mode, which builds the src from a
+    /// target. All src fields must have a target field and all values must implement `Clone`.
+    pub extract: bool,
+    /// Generate the `StructDiff` trait in borrowed mode, which builds the src from the changes
+    /// between two targets. All target fields must implement `PartialEq` and `Clone`.
+    pub diff: bool,
+    /// A function, which validates the target after a fallible merge.
+    /// Its signature is `fn(&Target) -> Result<(), E>`.
+    pub validate: Option<Path>,
+    /// The error type of fallible merges.
+    /// It must implement `From<struct_merge::MergeError>`.
+    pub error: Option<Type>,
+    /// Leave the target untouched, if the variant of a src enum differs from the target's variant.
+    /// By default, the target is replaced by the src variant.
+    pub keep_variant: bool,
+    /// Type aliases of `Option`, which are treated like an `Option`.
+    /// E.g. `#[merge(option_aliases(MaybeString))]` for `type MaybeString = Option<String>`.
+    pub option_aliases: Vec<Ident>,
+    /// Error on fields, whose types might be aliases of `Option`, if soft merges are generated.
+    pub deny_aliases: bool,
+    /// Merge fields, whose types seem to differ, and let the compiler check their types.
+    pub defer_type_checks: bool,
+}
+
+/// Options that are set via `#[merge(...)]` attributes on the fields of the src struct.
+#[derive(Clone, Default)]
+pub struct FieldOptions {
+    /// A function, which validates the converted value of this field during a fallible merge.
+    /// Its signature is `fn(&T) -> Result<(), E>`.
+    pub validate: Option<Path>,
+    /// The index of the target field in a tuple struct, e.g. `#[merge(index = 0)]`.
+    pub index: Option<Index>,
+    /// The name of the target field in a struct with named fields, e.g. `#[merge(name = "id")]`.
+    pub name: Option<Ident>,
+    /// Whether this field is an `Option`, which overrides the detection via its type.
+    /// Set via `#[merge(optional)]` and `#[merge(required)]`.
+    pub optional: Option<bool>,
+    /// Whether the target field is an `Option`, which overrides the detection via its type.
+    /// Set via `#[merge(target_optional)]` and `#[merge(target_required)]`.
+    pub target_optional: Option<bool>,
+    /// Merge this field into the target field via its own merge implementation.
+    /// Set via `#[merge(nested)]`.
+    pub nested: bool,
+    /// The partial struct of this field's type, which is used in the partial struct that's
+    /// generated by `struct_merge_partial`, e.g. `#[merge(partial = "AddressPatch")]`.
+    pub partial: Option<Type>,
+    /// Copy the value of this field instead of cloning it. Set via `#[merge(copy)]`.
+    pub copy: bool,
+    /// Convert the referenced value of this field via `ToOwned`, e.g. `&'a str` into `String`.
+    /// Set via `#[merge(to_owned)]`.
+    pub to_owned: bool,
+    /// A function, which converts a reference to the value of this field into the target's type.
+    /// Its signature is `fn(&T) -> U`. Set via `#[merge(ref_with = "path::to::fn")]`.
+    pub ref_with: Option<Path>,
+}
+
+/// Parse the struct-level `#[merge(...)]` attributes of the src struct.
+pub fn parse_struct_options(attrs: &[Attribute]) -> Result<StructOptions, TokenStream> {
+    let mut options = StructOptions::default();
+
+    for nested in parse_helper_attributes(attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("track_changes") => {
+                options.track_changes = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("preview") => {
+                options.preview = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("extract") => {
+                options.extract = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("diff") => {
+                options.diff = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("keep_variant") => {
+                options.keep_variant = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("deny_aliases") => {
+                options.deny_aliases = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("defer_type_checks") => {
+                options.defer_type_checks = true;
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("option_aliases") => {
+                for alias in list.nested {
+                    let ident = match &alias {
+                        NestedMeta::Meta(Meta::Path(path)) => path.get_ident(),
+                        _ => None,
+                    };
+                    match ident {
+                        Some(ident) => options.option_aliases.push(ident.clone()),
+                        None => return Err(err!(alias, "Expected the name of a type alias.")),
+                    }
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("validate") =>
+            {
+                options.validate = Some(parse_lit_str(&name_value.lit)?);
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("error") => {
+                options.error = Some(parse_lit_str(&name_value.lit)?);
+            }
+            _ => return Err(err!(nested, "Unknown struct_merge struct option.")),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Parse the `#[merge(...)]` attributes of a single field of the src struct.
+pub fn parse_field_options(attrs: &[Attribute]) -> Result<FieldOptions, TokenStream> {
+    let mut options = FieldOptions::default();
+
+    for nested in parse_helper_attributes(attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("validate") =>
+            {
+                options.validate = Some(parse_lit_str(&name_value.lit)?);
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("index") => {
+                options.index = match &name_value.lit {
+                    Lit::Int(lit_int) => Some(Index {
+                        index: lit_int
+                            .base10_parse()
+                            .map_err(|error| error.to_compile_error())?,
+                        span: lit_int.span(),
+                    }),
+                    lit => return Err(err!(lit, "Expected an integer literal.")),
+                };
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("name") => {
+                options.name = Some(parse_lit_str(&name_value.lit)?);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("optional") => {
+                options.optional = Some(true);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("required") => {
+                options.optional = Some(false);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("target_optional") => {
+                options.target_optional = Some(true);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("target_required") => {
+                options.target_optional = Some(false);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("nested") => {
+                options.nested = true;
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("partial") =>
+            {
+                options.partial = Some(parse_lit_str(&name_value.lit)?);
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("copy") => {
+                options.copy = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("to_owned") => {
+                options.to_owned = true;
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("ref_with") =>
+            {
+                options.ref_with = Some(parse_lit_str(&name_value.lit)?);
+            }
+            _ => return Err(err!(nested, "Unknown struct_merge field option.")),
+        }
+    }
+
+    let accesses = [options.copy, options.to_owned, options.ref_with.is_some()];
+    if accesses.iter().filter(|access| **access).count() > 1 {
+        return Err(err!(
+            attrs[0],
+            "Only one of 'copy', 'to_owned' and 'ref_with' can be set."
+        ));
+    }
+
+    Ok(options)
+}
+
+/// Parse the content of a string literal, such as the function path in `validate = "path"`.
+fn parse_lit_str<T: Parse>(lit: &Lit) -> Result<T, TokenStream> {
+    match lit {
+        Lit::Str(lit_str) => lit_str.parse().map_err(|error| error.to_compile_error()),
+        _ => Err(err!(lit, "Expected a string literal.")),
+    }
+}
+
+/// Collect the contents of all `#[merge(...)]` attributes in the given list.
+fn parse_helper_attributes(attrs: &[Attribute]) -> Result<Vec<NestedMeta>, TokenStream> {
+    let mut nested = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident(HELPER_ATTRIBUTE) {
+            continue;
+        }
+
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => nested.extend(list.nested),
+            Ok(meta) => {
+                return Err(err!(
+                    meta,
+                    "Expected a list of options, such as '#[merge(track_changes)]'."
+                ))
+            }
+            Err(error) => return Err(error.to_compile_error()),
+        }
+    }
+
+    Ok(nested)
+}
+
+/// Remove all `#[merge(...)]` attributes from the struct or enum and its fields, so the compiler
+/// doesn't stumble upon them.
+///
+/// If there are further macros of this crate on the item, the attributes are left untouched.
+/// These macros need the attributes as well and will remove them later on.
+pub fn strip_helper_attributes(item: &mut Item) {
+    let (attrs, fields): (_, Vec<&mut Field>) = match item {
+        Item::Struct(item_struct) => (
+            &mut item_struct.attrs,
+            item_struct.fields.iter_mut().collect(),
+        ),
+        Item::Enum(item_enum) => (
+            &mut item_enum.attrs,
+            item_enum
+                .variants
+                .iter_mut()
+                .flat_map(|variant| variant.fields.iter_mut())
+                .collect(),
+        ),
+        _ => return,
+    };
+
+    let has_further_macros = attrs.iter().any(|attr| {
+        attr.path
+            .segments
+            .last()
+            .map(|segment| MACRO_NAMES.iter().any(|name| segment.ident == name))
+            .unwrap_or(false)
+    });
+    if has_further_macros {
+        return;
+    }
+
+    attrs.retain(|attr| !is_helper_attribute(attr));
+    for field in fields {
+        field.attrs.retain(|attr| !is_helper_attribute(attr));
+    }
+}
+
+/// Check whether the attribute is one of our `#[merge(...)]` helper attributes.
+pub fn is_helper_attribute(attr: &Attribute) -> bool {
+    attr.path.is_ident(HELPER_ATTRIBUTE)
+}
@@ -1,133 +1,124 @@
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use syn::Field;
+use quote::quote;
 
 use super::*;
 
 /// Generate the implementation of [struct_merge::StructMergeRef] for given structs.
-pub(crate) fn impl_borrowed(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
+pub(crate) fn impl_borrowed(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    let mut errors = TokenStream::new();
+    // The src fields are consumed by the pairs, but all of them are needed to build the src.
+    let matched_fields: Vec<(Member, bool)> = fields
+        .iter()
+        .map(|field| {
+            let (src_field_type, _) = field.field_types(&params.options.option_aliases);
+            (
+                field.src_member.clone(),
+                matches!(src_field_type, FieldType::Optional { .. }),
+            )
+        })
+        .collect();
+    let pairs = determine_field_pairs(fields, params.type_context(), &mut errors);
+
     let mut functions_tokens = TokenStream::new();
 
-    let stream = merge_ref(params, fields.clone());
+    let stream = merge_ref(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_ref_soft(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_ref_report(params, &pairs);
     functions_tokens.extend(vec![stream]);
 
-    let stream = merge_ref_soft(params, fields);
+    let stream = merge_ref_soft_report(params, &pairs);
     functions_tokens.extend(vec![stream]);
 
-    let src_ident = &params.src_struct.ident;
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
     let target_path = &params.target_path;
-    quote! {
-        impl struct_merge::StructMergeIntoRef<#target_path> for #src_ident {
+    let mut tokens = quote! {
+        #errors
+
+        impl #impl_generics struct_merge::StructMergeIntoRef<#target_path> for #src_type #where_clause {
             #functions_tokens
         }
+    };
+
+    if params.options.preview {
+        let stream = merge_preview(params, &pairs);
+        tokens.extend(vec![quote! {
+            impl #impl_generics struct_merge::StructMergeIntoPreview<#target_path> for #src_type #where_clause {
+                #stream
+            }
+        }]);
     }
+
+    // These implementations are only generated, if all src fields can be built.
+    if params.options.extract {
+        match extract_from(params, &matched_fields, &pairs) {
+            Ok(stream) => tokens.extend(vec![quote! {
+                impl #impl_generics struct_merge::StructExtractFrom<#target_path> for #src_type #where_clause {
+                    #stream
+                }
+            }]),
+            Err(extract_errors) => tokens.extend(vec![extract_errors]),
+        }
+    }
+
+    if params.options.diff {
+        match diff(params, &matched_fields, &pairs) {
+            Ok(stream) => tokens.extend(vec![quote! {
+                impl #impl_generics struct_merge::StructDiff<#target_path> for #src_type #where_clause {
+                    #stream
+                }
+            }]),
+            Err(diff_errors) => tokens.extend(vec![diff_errors]),
+        }
+    }
+
+    tokens
 }
 
-/// Generate the [struct_merge::StructMergeRef::merge_ref] function for given structs.
+/// The src is borrowed, so its values need to be cloned, unless they're copied or converted via
+/// `#[merge(copy)]`, `#[merge(to_owned)]` or `#[merge(ref_with = "fn")]`.
+/// `Clone::clone` is called explicitly, so references are copied instead of dereferenced.
+fn src_access(pair: &FieldPair) -> SrcAccess {
+    let src_member = &pair.src_member;
+    let src = SrcAccess {
+        value: quote!(Clone::clone(&self.#src_member)),
+        option: quote!(self.#src_member.as_ref()),
+        inner: quote!(Clone::clone(value)),
+        reference: Some(quote!(&self.#src_member)),
+    };
+    apply_ref_access(pair, src, &quote!(self.#src_member))
+}
+
+/// Generate the code that merges all fields of `&self` into `target`.
 ///
-/// All fields must implement `Clone`.
-fn merge_ref(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
+/// All fields must implement `Clone`, unless they're copied or converted.
+fn merge_code(pairs: &[FieldPair], flags: MergeFlags) -> TokenStream {
     let mut merge_code = TokenStream::new();
-    for (src_field, target_field) in fields {
-        let src_field_ident = src_field.ident;
-        let target_field_ident = target_field.ident;
-
-        // Find out, whether the fields are optional or not.
-        let src_field_type = determine_field_type(src_field.ty);
-        let target_field_type = determine_field_type(target_field.ty);
-
-        let snippet = match (src_field_type, target_field_type) {
-            // Both fields have the same type
-            (FieldType::Normal(src_type), FieldType::Normal(target_type)) => {
-                equal_type_or_continue!(
-                    src_type,
-                    target_type,
-                    "",
-                    quote! {
-                        target.#target_field_ident = self.#src_field_ident.clone();
-                    }
-                )
-            }
-            // The src is optional and needs to be `Some(T)` to be merged.
-            (
-                FieldType::Optional {
-                    inner: src_type, ..
-                },
-                FieldType::Normal(target_type),
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    target_type,
-                    "Inner ",
-                    quote! {
-                        if let Some(value) = self.#src_field_ident.as_ref() {
-                            target.#target_field_ident = value.clone();
-                        }
-                    }
-                )
-            }
-            // The target is optional and needs to be wrapped in `Some(T)` to be merged.
-            (
-                FieldType::Normal(src_type),
-                FieldType::Optional {
-                    inner: target_type, ..
-                },
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    target_type,
-                    "",
-                    quote! {
-                        self.#target_field_ident = Some(src.#src_field_ident.clone());
-                    }
-                )
-            }
-            // Both fields are optional. It can now be either of these:
-            // - (Option<T>, Option<T>)
-            // - (Option<Option<T>>, Option<T>)
-            // - (Option<T>, Option<Option<T>>)
-            (
-                FieldType::Optional {
-                    inner: inner_src_type,
-                    outer: outer_src_type,
-                },
-                FieldType::Optional {
-                    inner: inner_target_type,
-                    outer: outer_target_type,
-                },
-            ) => {
-                // Handling the (Option<T>, Option<T>) case
-                if is_equal_type(&inner_src_type, &inner_target_type) {
-                    quote! {
-                        target.#target_field_ident = self.#src_field_ident.clone();
-                    }
-                // Handling the (Option<Option<<T>>, Option<T>) case
-                } else if is_equal_type(&inner_src_type, &outer_target_type) {
-                    quote! {
-                        if let Some(value) = self.#src_field_ident.as_ref() {
-                            target.#target_field_ident = value.clone();
-                        }
-                    }
-                // Handling the (Option<<T>, Option<Option<T>)> case
-                } else {
-                    equal_type_or_continue!(
-                        outer_src_type,
-                        inner_target_type,
-                        "",
-                        quote! {
-                            target.#target_field_ident = Some(self.#src_field_ident.clone());
-                        }
-                    )
-                }
-            }
-            // Skip anything where either of the fields are invalid
-            (FieldType::Invalid, _) | (_, FieldType::Invalid) => continue,
-        };
+    for pair in pairs {
+        let target_member = &pair.target_member;
+
+        let target = quote!(target.#target_member);
 
-        merge_code.extend(vec![snippet]);
+        if let Some(snippet) = merge_field(pair, &src_access(pair), &target, flags) {
+            merge_code.extend(vec![snippet]);
+        }
     }
 
-    let merge_code = merge_code.to_token_stream();
+    merge_code
+}
+
+/// Generate the [struct_merge::StructMergeRef::merge_ref] function for given structs.
+fn merge_ref(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
 
     let target_path = &params.target_path;
     quote! {
@@ -138,97 +129,409 @@ fn merge_ref(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
 }
 
 /// Generate the [struct_merge::StructMergeRef::merge_ref_soft] function for given structs.
+fn merge_ref_soft(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_ref_soft(&self, target: &mut #target_path) {
+            #merge_code
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergeRef::merge_ref_report] function for given structs.
+fn merge_ref_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_ref_report(&self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
+            #merge_code
+            report
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergeRef::merge_ref_soft_report] function for given
+/// structs.
+fn merge_ref_soft_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_ref_soft_report(&self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
+            #merge_code
+            report
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergePreview::merge_preview] function for given structs.
 ///
-/// All fields must implement `Clone`.
-fn merge_ref_soft(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
-    let mut merge_code = TokenStream::new();
-    for (src_field, target_field) in fields {
-        let src_field_ident = src_field.ident;
-        let target_field_ident = target_field.ident;
-
-        // Find out, whether the fields are optional or not.
-        let src_field_type = determine_field_type(src_field.ty);
-        let target_field_type = determine_field_type(target_field.ty);
-
-        let snippet = match (src_field_type, target_field_type) {
-            // Soft merge only applies if the target field is `Optional`.
-            (FieldType::Normal(_), FieldType::Normal(_))
-            | (FieldType::Optional { .. }, FieldType::Normal(_)) => continue,
-            // The target is optional and needs to be wrapped in `Some(T)` to be merged.
-            (
-                FieldType::Normal(src_type),
-                FieldType::Optional {
-                    inner: target_type, ..
-                },
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    target_type,
-                    "",
-                    quote! {
-                        if target.#target_field_ident.is_none() {
-                            target.#target_field_ident = Some(self.#src_field_ident.clone());
+/// All fields must implement `PartialEq` and `Debug`.
+/// Arrays, smart pointers, nested fields, patches and converted fields must implement `Clone` as
+/// well.
+/// The target isn't touched, we only compare the current values with the ones that would be
+/// merged.
+fn merge_preview(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let mut preview_code = TokenStream::new();
+    for pair in pairs {
+        let src_member = &pair.src_member;
+        let target_member = &pair.target_member;
+        let name = &pair.name;
+
+        // Arrays, smart pointers, nested fields, patches and converted values are merged into a
+        // clone of the target field, which is then compared to the actual target field.
+        let conversion = &pair.conversion;
+        if conversion.elements
+            || conversion.pointer.is_some()
+            || conversion.nested
+            || conversion.patch
+            || pair.ref_access.converts()
+        {
+            let flags = MergeFlags {
+                soft: false,
+                report: false,
+                track_changes: false,
+            };
+            let src = src_access(pair);
+            if let Some(merge_code) = merge_field(pair, &src, &quote!(merged), flags) {
+                preview_code.extend(vec![quote! {
+                    {
+                        let mut merged = target.#target_member.clone();
+                        #merge_code
+                        if merged != target.#target_member {
+                            diff.fields.push(struct_merge::FieldDiff {
+                                name: #name,
+                                old: format!("{:?}", target.#target_member),
+                                new: format!("{:?}", merged),
+                            });
                         }
                     }
-                )
+                }]);
             }
-            // Both fields are optional. It can now be either of these:
-            // - (Option<T>, Option<T>)
-            // - (Option<Option<T>>, Option<T>)
-            // - (Option<T>, Option<Option<T>>)
-            (
-                FieldType::Optional {
-                    inner: inner_src_type,
-                    outer: outer_src_type,
-                },
-                FieldType::Optional {
-                    inner: inner_target_type,
-                    outer: outer_target_type,
-                },
-            ) => {
-                // Handling the (Option<T>, Option<T>) case
-                if is_equal_type(&inner_src_type, &inner_target_type) {
-                    quote! {
-                        if target.#target_field_ident.is_none() {
-                            target.#target_field_ident = self.#src_field_ident.clone();
-                        }
-                    }
-                // Handling the (Option<Option<<T>>, Option<T>) case
-                } else if is_equal_type(&inner_src_type, &outer_target_type) {
-                    quote! {
-                        if let Some(value) = self.#src_field_ident.as_ref() {
-                            if target.#target_field_ident.is_none() {
-                                target.#target_field_ident = value.clone();
-                            }
-                        }
+            continue;
+        }
+
+        preview_code.extend(vec![pair.type_checks.clone()]);
+        let snippet = match pair.conversion.conversion {
+            Conversion::Direct => quote! {
+                if target.#target_member != self.#src_member {
+                    diff.fields.push(struct_merge::FieldDiff {
+                        name: #name,
+                        old: format!("{:?}", target.#target_member),
+                        new: format!("{:?}", self.#src_member),
+                    });
+                }
+            },
+            Conversion::Wrap => quote! {
+                if target.#target_member.as_ref() != Some(&self.#src_member) {
+                    diff.fields.push(struct_merge::FieldDiff {
+                        name: #name,
+                        old: format!("{:?}", target.#target_member),
+                        new: format!("{:?}", Some(&self.#src_member)),
+                    });
+                }
+            },
+            Conversion::Unwrap => quote! {
+                if let Some(value) = self.#src_member.as_ref() {
+                    if target.#target_member != *value {
+                        diff.fields.push(struct_merge::FieldDiff {
+                            name: #name,
+                            old: format!("{:?}", target.#target_member),
+                            new: format!("{:?}", value),
+                        });
                     }
-                // Handling the (Option<<T>, Option<Option<T>)> case
-                } else {
-                    equal_type_or_continue!(
-                        outer_src_type,
-                        inner_target_type,
-                        "",
-                        quote! {
-                            if target.#target_field_ident.is_none() {
-                                target.#target_field_ident = Some(self.#src_field_ident.clone());
-                            }
-                        }
-                    )
                 }
-            }
-            // Skip anything where either of the fields are invalid
-            (FieldType::Invalid, _) | (_, FieldType::Invalid) => continue,
+            },
         };
 
-        merge_code.extend(vec![snippet]);
+        preview_code.extend(vec![snippet]);
     }
 
-    let merge_code = merge_code.to_token_stream();
-
     let target_path = &params.target_path;
     quote! {
-        fn merge_into_ref_soft(&self, target: &mut #target_path) {
-            #merge_code
+        fn merge_into_preview(&self, target: &#target_path) -> struct_merge::MergeDiff {
+            #[allow(unused_mut)]
+            let mut diff = struct_merge::MergeDiff::default();
+            #preview_code
+            diff
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructExtractFrom::extract_from] function for given structs.
+///
+/// Each src field is built from a clone of its target field, which reverts the conversion of the
+/// merge.
+fn extract_from(
+    params: &Parameters,
+    matched_fields: &[(Member, bool)],
+    pairs: &[FieldPair],
+) -> Result<TokenStream, TokenStream> {
+    let fields_code = build_src_fields(params, matched_fields, pairs, |pair, src_optional| {
+        let target_member = &pair.target_member;
+        let value = extract_value(pair, src_optional, &quote!(target.#target_member))?;
+        Ok(patch_value(pair, value))
+    })?;
+
+    let target_path = &params.target_path;
+    Ok(quote! {
+        fn extract_from(target: &#target_path) -> Self {
+            Self {
+                #fields_code
+            }
+        }
+    })
+}
+
+/// Generate the [struct_merge::StructDiff::diff] function for given structs.
+///
+/// Optional src fields are only set, if their target field changed from `old` to `new`.
+/// All other src fields are built from `new`, just like during extraction.
+/// All target fields must implement `PartialEq` and `Clone`.
+fn diff(
+    params: &Parameters,
+    matched_fields: &[(Member, bool)],
+    pairs: &[FieldPair],
+) -> Result<TokenStream, TokenStream> {
+    let fields_code = build_src_fields(params, matched_fields, pairs, |pair, src_optional| {
+        Ok(patch_value(pair, diff_value(pair, src_optional)?))
+    })?;
+
+    let target_path = &params.target_path;
+    Ok(quote! {
+        fn diff(old: &#target_path, new: &#target_path) -> Self {
+            Self {
+                #fields_code
+            }
         }
+    })
+}
+
+/// Generate the initializers of all src fields via `value`, which is called with the pair of
+/// each src field and whether the src field is optional.
+///
+/// `matched_fields` contains the members of all src fields with a target field and whether
+/// they're optional.
+/// Src fields without a target field or that cannot be merged cannot be built and thereby
+/// result in errors, as do the error messages returned by `value`.
+fn build_src_fields(
+    params: &Parameters,
+    matched_fields: &[(Member, bool)],
+    pairs: &[FieldPair],
+    value: impl Fn(&FieldPair, bool) -> Result<TokenStream, String>,
+) -> Result<TokenStream, TokenStream> {
+    let mut errors = TokenStream::new();
+    let mut fields_code = TokenStream::new();
+    for (src_member, src_field) in members(&params.src_struct.fields) {
+        let src_optional = match matched_fields
+            .iter()
+            .find(|(member, _)| *member == src_member)
+        {
+            Some((_, src_optional)) => *src_optional,
+            None => {
+                errors.extend(vec![err!(
+                    src_field,
+                    "Field '{}' doesn't have a target field and cannot be built from it.",
+                    src_member.to_token_stream()
+                )]);
+                continue;
+            }
+        };
+
+        // Fields that cannot be merged, e.g. because of invalid types, cannot be built either.
+        let pair = match pairs.iter().find(|pair| pair.src_member == src_member) {
+            Some(pair) => pair,
+            None => {
+                errors.extend(vec![err!(
+                    src_field,
+                    "Field '{}' cannot be merged into its target field and thereby cannot be \
+                    built from it.",
+                    src_member.to_token_stream()
+                )]);
+                continue;
+            }
+        };
+
+        match value(pair, src_optional) {
+            Ok(value) => fields_code.extend(vec![quote!(#src_member: #value,)]),
+            Err(message) => errors.extend(vec![err!(src_field, "{}", message)]),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
     }
+
+    Ok(fields_code)
+}
+
+/// Patch fields are built from their `Option<Option<T>>` counterpart.
+fn patch_value(pair: &FieldPair, value: TokenStream) -> TokenStream {
+    if pair.conversion.patch {
+        return quote!(struct_merge::Patch::from(#value));
+    }
+
+    value
+}
+
+/// Generate the expression, that builds the value of a src field from the `target` field
+/// expression.
+///
+/// An error message is returned, if the src value cannot be built, e.g. because the target field
+/// is optional but the src field isn't.
+fn extract_value(
+    pair: &FieldPair,
+    src_optional: bool,
+    target: &TokenStream,
+) -> Result<TokenStream, String> {
+    let conversion = &pair.conversion;
+
+    if pair.ref_access.converts() {
+        return Err(format!(
+            "Field '{}' is converted via 'to_owned' or 'ref_with' and cannot be built from its \
+            target field.",
+            pair.name
+        ));
+    }
+
+    // Build the src value from a reference to the target value.
+    let revert = |reference: TokenStream| match (conversion.nested, conversion.pointer) {
+        (true, Some(_)) => quote!(struct_merge::StructExtractFrom::extract_from(&**#reference)),
+        (true, None) => quote!(struct_merge::StructExtractFrom::extract_from(#reference)),
+        (false, Some(pointer)) => pointer.revert(&reference),
+        (false, None) => quote!(Clone::clone(#reference)),
+    };
+
+    if conversion.elements {
+        return match conversion.conversion {
+            Conversion::Direct => Ok(quote!(Clone::clone(&#target))),
+            Conversion::Unwrap => Ok(quote!(Clone::clone(&#target).map(Some))),
+            Conversion::Wrap => Err(format!(
+                "Field '{}' cannot be built from an array with optional elements.",
+                pair.name
+            )),
+        };
+    }
+
+    // The value of optional targets is only reverted, if it exists.
+    let value = if conversion.target_optional && (conversion.nested || conversion.pointer.is_some())
+    {
+        let value = revert(quote!(value));
+        quote!(#target.as_ref().map(|value| #value))
+    } else {
+        revert(quote!(&#target))
+    };
+
+    match (conversion.conversion, conversion.target_optional) {
+        (Conversion::Direct, _) => Ok(value),
+        (Conversion::Unwrap, _) => Ok(quote!(Some(#value))),
+        // `Option<T>` into `Option<Option<T>>`
+        (Conversion::Wrap, true) if src_optional && conversion.pointer.is_none() => {
+            Ok(quote!(Clone::clone(&#target).flatten()))
+        }
+        (Conversion::Wrap, _) => Err(format!(
+            "Field '{}' cannot be built from an optional target field.",
+            pair.name
+        )),
+    }
+}
+
+/// Generate the expression, that builds the value of a src field from the changes between the
+/// `old` and `new` target field.
+///
+/// Optional src fields are `None`, if the target field didn't change.
+/// Src fields, whose merges never clear the target field, cannot represent every change and
+/// thereby result in an error message. The same goes for nested fields with optional targets,
+/// as their merges never create a value.
+fn diff_value(pair: &FieldPair, src_optional: bool) -> Result<TokenStream, String> {
+    let target_member = &pair.target_member;
+    let old = quote!(old.#target_member);
+    let new = quote!(new.#target_member);
+    let conversion = &pair.conversion;
+
+    if matches!(conversion.conversion, Conversion::Wrap) {
+        return Err(format!(
+            "Field '{}' cannot be diffed, as merging it never clears its optional target field.",
+            pair.name
+        ));
+    }
+
+    if conversion.nested {
+        // Targets behind a smart pointer are diffed via references to their values.
+        let diff = |old: TokenStream, new: TokenStream| match conversion.pointer {
+            Some(_) => quote!(struct_merge::StructDiff::diff(&**#old, &**#new)),
+            None => quote!(struct_merge::StructDiff::diff(#old, #new)),
+        };
+
+        return Ok(match (conversion.conversion, conversion.target_optional) {
+            // A value, that's only in the new target, couldn't be replayed.
+            (_, true) => {
+                return Err(format!(
+                    "Nested field '{}' cannot be diffed, as nested merges never create a value \
+                    in an empty optional target field. Merge the field as a whole instead.",
+                    pair.name
+                ))
+            }
+            (Conversion::Unwrap, false) => {
+                let diff = diff(quote!(&#old), quote!(&#new));
+                quote! {
+                    if #old != #new {
+                        Some(#diff)
+                    } else {
+                        None
+                    }
+                }
+            }
+            _ => diff(quote!(&#old), quote!(&#new)),
+        });
+    }
+
+    if conversion.elements && matches!(conversion.conversion, Conversion::Unwrap) {
+        // Unchanged elements are `None`.
+        return Ok(quote! {
+            {
+                let mut diff = Clone::clone(&#new).map(Some);
+                for (value, old) in diff.iter_mut().zip(#old.iter()) {
+                    if value.as_ref() == Some(old) {
+                        *value = None;
+                    }
+                }
+                diff
+            }
+        });
+    }
+
+    let value = extract_value(pair, src_optional, &new)?;
+    Ok(match conversion.conversion {
+        Conversion::Unwrap => quote! {
+            if #old != #new {
+                #value
+            } else {
+                None
+            }
+        },
+        _ => value,
+    })
 }
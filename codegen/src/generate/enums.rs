@@ -0,0 +1,390 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Path, PathArguments, Variant};
+
+use super::*;
+
+/// A variant of the src enum and the variant of the target enum with the same name.
+struct VariantPair {
+    src: Variant,
+    target: Variant,
+    pairs: Vec<FieldPair>,
+}
+
+/// Generate the implementation of [struct_merge::StructMerge] or [struct_merge::StructMergeRef]
+/// for given enums.
+///
+/// If the src and the target have the same variant, their fields are merged just like the fields
+/// of structs. Otherwise, the target is replaced by the src variant, unless
+/// `#[merge(keep_variant)]` is set.
+pub(crate) fn impl_enum(mode: &Mode, params: &EnumParameters) -> TokenStream {
+    let mut errors = TokenStream::new();
+    let variants = match_variants(params, &mut errors);
+    let owned = matches!(mode, Mode::Owned);
+
+    let (trait_name, function_names) = if owned {
+        (
+            quote!(StructMergeInto),
+            [
+                "merge_into",
+                "merge_into_soft",
+                "merge_into_report",
+                "merge_into_soft_report",
+            ],
+        )
+    } else {
+        (
+            quote!(StructMergeIntoRef),
+            [
+                "merge_into_ref",
+                "merge_into_ref_soft",
+                "merge_into_ref_report",
+                "merge_into_ref_soft_report",
+            ],
+        )
+    };
+
+    let mut functions_tokens = TokenStream::new();
+    for (index, name) in function_names.iter().enumerate() {
+        let flags = MergeFlags {
+            soft: index % 2 == 1,
+            report: index >= 2,
+            track_changes: index >= 2 && params.options.track_changes,
+        };
+        let name = Ident::new(name, proc_macro2::Span::call_site());
+        // All functions would report the same errors, so only keep them once.
+        let mut function_errors = TokenStream::new();
+        let stream = merge_function(params, &variants, owned, flags, &name, &mut function_errors);
+        functions_tokens.extend(vec![stream]);
+        if index == 0 {
+            errors.extend(vec![function_errors]);
+        }
+    }
+
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
+    let target_path = &params.target_path;
+    quote! {
+        #errors
+
+        impl #impl_generics struct_merge::#trait_name<#target_path> for #src_type #where_clause {
+            #functions_tokens
+        }
+    }
+}
+
+/// Find the respective target variant for each src variant and pair their fields.
+///
+/// Src variants without a respective target variant result in an error.
+fn match_variants(params: &EnumParameters, errors: &mut TokenStream) -> Vec<VariantPair> {
+    let target_ident = &params.target_enum.ident;
+
+    let mut variants = Vec::new();
+    for src in params.src_enum.variants.iter() {
+        let target = params
+            .target_enum
+            .variants
+            .iter()
+            .find(|target| target.ident == src.ident);
+        let target = match target {
+            Some(target) => target,
+            None => {
+                errors.extend(vec![err!(
+                    src,
+                    "Enum {} doesn't have a variant '{}'.",
+                    target_ident,
+                    src.ident
+                )]);
+                continue;
+            }
+        };
+
+        let variant_ident = &target.ident;
+        let fields = match_fields(
+            &src.fields,
+            &target.fields,
+            &quote!(#target_ident::#variant_ident),
+            &params.target_options,
+            errors,
+        );
+        if params.options.deny_aliases {
+            errors.extend(vec![deny_aliases(
+                &fields,
+                &params.generics,
+                &params.options.option_aliases,
+            )]);
+        }
+        let pairs = determine_field_pairs(fields, params.type_context(), errors);
+        let pairs = reject_conversions(pairs, "enums", errors);
+
+        variants.push(VariantPair {
+            src: src.clone(),
+            target: target.clone(),
+            pairs,
+        });
+    }
+
+    variants
+}
+
+/// Generate a single merge function, e.g. `merge_into_soft`, for the given enums.
+fn merge_function(
+    params: &EnumParameters,
+    variants: &[VariantPair],
+    owned: bool,
+    flags: MergeFlags,
+    name: &Ident,
+    errors: &mut TokenStream,
+) -> TokenStream {
+    // Variants are constructed and matched without their generic arguments.
+    let mut target_base = params.target_path.clone();
+    if let Some(segment) = target_base.segments.last_mut() {
+        segment.arguments = PathArguments::None;
+    }
+
+    let mut arms = TokenStream::new();
+    for variant in variants {
+        let arm = merge_variant(params, variant, &target_base, owned, flags, errors);
+        arms.extend(vec![arm]);
+    }
+
+    let receiver = if owned { quote!(self) } else { quote!(&self) };
+    let target_path = &params.target_path;
+    if flags.report {
+        quote! {
+            #[allow(irrefutable_let_patterns)]
+            fn #name(#receiver, target: &mut #target_path) -> struct_merge::MergeReport {
+                #[allow(unused_mut)]
+                let mut report = struct_merge::MergeReport::default();
+                match self {
+                    #arms
+                }
+                report
+            }
+        }
+    } else {
+        quote! {
+            #[allow(irrefutable_let_patterns)]
+            fn #name(#receiver, target: &mut #target_path) {
+                match self {
+                    #arms
+                }
+            }
+        }
+    }
+}
+
+/// Generate the match arm, that merges a single src variant into the target.
+///
+/// Fields of the same variant are merged via the usual rules.
+/// A different target variant is replaced, unless this is a soft merge or
+/// `#[merge(keep_variant)]` is set.
+/// The replacement is skipped, if an optional src value for a required target field is `None`.
+fn merge_variant(
+    params: &EnumParameters,
+    variant: &VariantPair,
+    target_base: &Path,
+    owned: bool,
+    flags: MergeFlags,
+    errors: &mut TokenStream,
+) -> TokenStream {
+    let src_variant = &variant.src.ident;
+    let target_variant = &variant.target.ident;
+    let variant_name = target_variant.to_string();
+
+    // The src values are bound to `src_{member}`, the target values to `target_{member}`.
+    let src_binding = |member: &Member| member_binding("src", member);
+    let target_binding = |member: &Member| member_binding("target", member);
+    let src_access = |member: &Member| {
+        let binding = src_binding(member);
+        if owned {
+            SrcAccess {
+                value: quote!(#binding),
+                option: quote!(#binding),
+                inner: quote!(value),
+                reference: None,
+            }
+        } else {
+            SrcAccess {
+                value: quote!(Clone::clone(#binding)),
+                option: quote!(#binding.as_ref()),
+                inner: quote!(Clone::clone(value)),
+                reference: Some(quote!(#binding)),
+            }
+        }
+    };
+
+    // Only bind the fields that are actually used.
+    let mut src_members = Vec::new();
+    let mut target_members = Vec::new();
+
+    // Merge all fields, if both have the same variant.
+    let mut merge_code = TokenStream::new();
+    for pair in variant.pairs.iter() {
+        let binding = target_binding(&pair.target_member);
+        let target = quote!((*#binding));
+        if let Some(snippet) = merge_field(pair, &src_access(&pair.src_member), &target, flags) {
+            merge_code.extend(vec![snippet]);
+            src_members.push(pair.src_member.clone());
+            target_members.push(pair.target_member.clone());
+        }
+    }
+
+    // The code that's executed if the target has another variant.
+    // A skipped replacement skips all fields of the target variant.
+    let target_names: Vec<String> = members(&variant.target.fields)
+        .iter()
+        .map(|(member, _)| member_name(member))
+        .collect();
+    let skip = if flags.report {
+        quote!(#(report.skipped.push(#target_names);)*)
+    } else {
+        TokenStream::new()
+    };
+    let other_code = if flags.soft || params.options.keep_variant {
+        skip
+    } else {
+        let mut field_members = Vec::new();
+        let mut field_values = Vec::new();
+        let mut patterns = Vec::new();
+        let mut options = Vec::new();
+        for (target_member, target_field) in members(&variant.target.fields) {
+            let pair = variant
+                .pairs
+                .iter()
+                .find(|pair| pair.target_member == target_member);
+            let value = match pair {
+                Some(pair) => {
+                    let access = src_access(&pair.src_member);
+                    let access = if pair.conversion.patch {
+                        patch_access(&access)
+                    } else {
+                        access
+                    };
+                    if !src_members.contains(&pair.src_member) {
+                        src_members.push(pair.src_member.clone());
+                    }
+                    let value = access.value;
+                    let conversion = &pair.conversion;
+                    match conversion.conversion {
+                        // Nested fields and optional elements cannot be converted.
+                        _ if conversion.nested => {
+                            errors.extend(vec![cannot_replace(variant, &target_member)]);
+                            continue;
+                        }
+                        Conversion::Unwrap
+                            if conversion.elements && !conversion.target_optional =>
+                        {
+                            errors.extend(vec![cannot_replace(variant, &target_member)]);
+                            continue;
+                        }
+                        // Arrays are converted element by element.
+                        Conversion::Direct if conversion.elements => value,
+                        Conversion::Wrap if conversion.elements => quote!(#value.map(Some)),
+                        Conversion::Unwrap if conversion.elements => {
+                            quote!(#value.map(Option::flatten))
+                        }
+                        Conversion::Direct => {
+                            convert_value(conversion, &value, conversion.target_optional)
+                        }
+                        Conversion::Wrap => {
+                            let value = convert_value(conversion, &value, false);
+                            quote!(Some(#value))
+                        }
+                        Conversion::Unwrap if conversion.target_optional => {
+                            convert_value(conversion, &quote!(#value.flatten()), true)
+                        }
+                        // The target can only be replaced, if the src value exists.
+                        Conversion::Unwrap => {
+                            let binding = member_binding("value", &target_member);
+                            patterns.push(quote!(Some(#binding)));
+                            options.push(access.option);
+                            let value = if owned {
+                                quote!(#binding)
+                            } else {
+                                quote!(Clone::clone(#binding))
+                            };
+                            convert_value(conversion, &value, false)
+                        }
+                    }
+                }
+                None => match determine_field_type(
+                    target_field.ty.clone(),
+                    &params.options.option_aliases,
+                ) {
+                    FieldType::Optional { .. } => quote!(None),
+                    _ => {
+                        errors.extend(vec![err!(
+                            variant.src,
+                            "Variant {} cannot replace the target, as field '{}' is missing. \
+                            Use '#[merge(keep_variant)]' to keep the target's variant.",
+                            src_variant,
+                            target_member.to_token_stream()
+                        )]);
+                        continue;
+                    }
+                },
+            };
+            field_members.push(target_member);
+            field_values.push(value);
+        }
+
+        let mut replace_code = quote! {
+            *target = #target_base::#target_variant {
+                #(#field_members: #field_values,)*
+            };
+        };
+        // All fields of the new variant are written and thereby changed.
+        let field_names = field_members.iter().map(member_name);
+        if flags.report {
+            replace_code.extend(vec![quote! {
+                report.variant = Some(#variant_name);
+                #(report.written.push(#field_names);)*
+            }]);
+        }
+        if flags.track_changes {
+            let field_names = field_members.iter().map(member_name);
+            replace_code.extend(vec![quote! {
+                #(report.changed.push(#field_names);)*
+            }]);
+        }
+
+        if patterns.is_empty() {
+            replace_code
+        } else {
+            quote! {
+                if let (#(#patterns,)*) = (#(#options,)*) {
+                    #replace_code
+                } else {
+                    #skip
+                }
+            }
+        }
+    };
+
+    let src_bindings = src_members.iter().map(src_binding);
+    let target_bindings = target_members.iter().map(target_binding);
+    quote! {
+        Self::#src_variant { #(#src_members: #src_bindings,)* .. } => {
+            if let #target_base::#target_variant {
+                #(#target_members: #target_bindings,)* ..
+            } = &mut *target {
+                #merge_code
+            } else {
+                #other_code
+            }
+        }
+    }
+}
+
+/// The error for src fields, that cannot be converted into the respective target field, if the
+/// target is replaced by another variant.
+fn cannot_replace(variant: &VariantPair, target_member: &Member) -> TokenStream {
+    err!(
+        variant.src,
+        "Variant {} cannot replace the target, as field '{}' cannot be converted. \
+        Use '#[merge(keep_variant)]' to keep the target's variant.",
+        variant.src.ident,
+        target_member.to_token_stream()
+    )
+}
@@ -0,0 +1,308 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::*;
+use crate::attributes::parse_field_options;
+
+/// Generate the implementation of [struct_merge::TryStructMerge] for given structs.
+pub(crate) fn impl_fallible(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    // Differing types are converted via `TryFrom`, so there's nothing to defer.
+    if params.options.defer_type_checks {
+        return err!(
+            params.src_struct.ident,
+            "'defer_type_checks' isn't supported for fallible merges, as differing types are \
+            converted via TryFrom."
+        );
+    }
+
+    let stream = try_merge(params, fields);
+
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
+    let target_path = &params.target_path;
+    let error = match &params.options.error {
+        Some(error) => quote!(#error),
+        None => quote!(struct_merge::MergeError),
+    };
+    quote! {
+        impl #impl_generics struct_merge::TryStructMergeInto<#target_path> for #src_type #where_clause {
+            type Error = #error;
+
+            #stream
+        }
+    }
+}
+
+/// The different ways a converted value is merged into its target field.
+enum Assignment {
+    /// `target = value`
+    Direct,
+    /// `target = Some(value)`
+    Wrap,
+    /// `if let Some(value) = value { target = value }`
+    Unwrap,
+}
+
+/// Generate the [struct_merge::TryStructMerge::try_merge] function for given structs.
+///
+/// The merge happens in three steps:
+/// 1. All values are converted and validated.
+/// 2. All values are merged into the target.
+/// 3. The target is validated. If that fails, all previous values are restored.
+///
+/// That way, the target is left untouched if any error occurs.
+fn try_merge(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    let validate_target = params.options.validate.as_ref();
+    let context = params.type_context();
+
+    let mut convert_code = TokenStream::new();
+    let mut apply_code = TokenStream::new();
+    let mut restore_code = TokenStream::new();
+    for field in fields {
+        let name = field.name();
+        let src_member = &field.src_member;
+        let target_member = &field.target_member;
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&field.src.attrs).unwrap_or_default();
+        if options.nested {
+            convert_code.extend(vec![err!(
+                field.src,
+                "Nested fields aren't supported for fallible merges yet."
+            )]);
+            continue;
+        }
+        if options.to_owned || options.ref_with.is_some() {
+            convert_code.extend(vec![err!(
+                field.src,
+                "'to_owned' and 'ref_with' aren't supported for fallible merges yet."
+            )]);
+            continue;
+        }
+        if patch_inner(&field.src.ty, context).is_some() {
+            convert_code.extend(vec![err!(
+                field.src,
+                "Patch fields aren't supported for fallible merges yet."
+            )]);
+            continue;
+        }
+
+        // The converted value and the previous value of the target field.
+        let converted = member_binding("converted", target_member);
+        let previous = member_binding("previous", target_member);
+
+        // Convert a value via `TryFrom`, if the types of both fields differ.
+        // Types, that other merges convert differently, are rejected instead.
+        let mut unsupported = TokenStream::new();
+        let mut convert =
+            |value: TokenStream, src_type: &Type, target_type: &Type| -> TokenStream {
+                if is_equal_type(src_type, target_type, context) {
+                    return value;
+                }
+                if let Some(error) = unsupported_conversion(src_type, target_type, context) {
+                    unsupported.extend(vec![error]);
+                }
+                quote! {
+                    std::convert::TryFrom::try_from(#value)
+                        .map_err(|error| struct_merge::MergeError::new(Some(#name), error))?
+                }
+            };
+
+        if let Some(error) = field.slice_error() {
+            convert_code.extend(vec![error]);
+            continue;
+        }
+
+        // Find out, whether the fields are optional or not.
+        let (src_field_type, target_field_type) = field.field_types(&params.options.option_aliases);
+
+        let (assignment, value) = match (src_field_type, target_field_type) {
+            (FieldType::Normal(src_type), FieldType::Normal(target_type)) => (
+                Assignment::Direct,
+                convert(quote!(self.#src_member), &src_type, &target_type),
+            ),
+            (FieldType::Optional { inner, .. }, FieldType::Normal(target_type)) => (
+                Assignment::Unwrap,
+                convert(quote!(value), &inner, &target_type),
+            ),
+            (FieldType::Normal(src_type), FieldType::Optional { inner, .. }) => (
+                Assignment::Wrap,
+                convert(quote!(self.#src_member), &src_type, &inner),
+            ),
+            // Both fields are optional. It can now be either of these:
+            // - (Option<T>, Option<T>)
+            // - (Option<Option<T>>, Option<T>)
+            // - (Option<T>, Option<Option<T>>)
+            // - (Option<S>, Option<T>), which is converted element-wise.
+            (
+                FieldType::Optional {
+                    inner: inner_src_type,
+                    outer: outer_src_type,
+                },
+                FieldType::Optional {
+                    inner: inner_target_type,
+                    outer: outer_target_type,
+                },
+            ) => {
+                if is_equal_type(&inner_src_type, &inner_target_type, context) {
+                    (Assignment::Direct, quote!(self.#src_member))
+                } else if is_equal_type(&inner_src_type, &outer_target_type, context) {
+                    (Assignment::Unwrap, quote!(value))
+                } else if is_equal_type(&outer_src_type, &inner_target_type, context) {
+                    (Assignment::Wrap, quote!(self.#src_member))
+                } else {
+                    let value = convert(quote!(value), &inner_src_type, &inner_target_type);
+                    (
+                        Assignment::Direct,
+                        quote! {
+                            match self.#src_member {
+                                Some(value) => Some(#value),
+                                None => None,
+                            }
+                        },
+                    )
+                }
+            }
+            // Skip anything where either of the fields are invalid
+            (FieldType::Invalid, _) | (_, FieldType::Invalid) => continue,
+        };
+        if !unsupported.is_empty() {
+            convert_code.extend(vec![unsupported]);
+            continue;
+        }
+
+        // Validate the converted value, if a validation function is specified.
+        let value = match options.validate {
+            Some(validate) => quote! {
+                {
+                    let value = #value;
+                    #validate(&value)
+                        .map_err(|error| struct_merge::MergeError::new(Some(#name), error))?;
+                    value
+                }
+            },
+            None => value,
+        };
+
+        let (convert, apply, restore) = match assignment {
+            Assignment::Direct => (
+                quote! {
+                    let #converted = #value;
+                },
+                quote! {
+                    let #previous = std::mem::replace(&mut target.#target_member, #converted);
+                },
+                quote! {
+                    target.#target_member = #previous;
+                },
+            ),
+            Assignment::Wrap => (
+                quote! {
+                    let #converted = #value;
+                },
+                quote! {
+                    let #previous = target.#target_member.replace(#converted);
+                },
+                quote! {
+                    target.#target_member = #previous;
+                },
+            ),
+            Assignment::Unwrap => (
+                quote! {
+                    let #converted = match self.#src_member {
+                        Some(value) => Some(#value),
+                        None => None,
+                    };
+                },
+                quote! {
+                    let #previous = #converted
+                        .map(|value| std::mem::replace(&mut target.#target_member, value));
+                },
+                quote! {
+                    if let Some(previous) = #previous {
+                        target.#target_member = previous;
+                    }
+                },
+            ),
+        };
+
+        // The previous values only need to be remembered, if the target is validated.
+        let apply = if validate_target.is_some() {
+            apply
+        } else {
+            match assignment {
+                Assignment::Direct => quote! {
+                    target.#target_member = #converted;
+                },
+                Assignment::Wrap => quote! {
+                    target.#target_member = Some(#converted);
+                },
+                Assignment::Unwrap => quote! {
+                    if let Some(value) = #converted {
+                        target.#target_member = value;
+                    }
+                },
+            }
+        };
+
+        convert_code.extend(vec![convert]);
+        apply_code.extend(vec![apply]);
+        restore_code.extend(vec![restore]);
+    }
+
+    // Validate the merged target and restore the previous values on errors.
+    let validate_code = match validate_target {
+        Some(validate) => quote! {
+            if let Err(error) = #validate(&*target) {
+                #restore_code
+                return Err(struct_merge::MergeError::new(None, error).into());
+            }
+        },
+        None => quote! {},
+    };
+
+    let target_path = &params.target_path;
+    quote! {
+        fn try_merge_into(self, target: &mut #target_path) -> Result<(), Self::Error> {
+            #convert_code
+            #apply_code
+            #validate_code
+
+            Ok(())
+        }
+    }
+}
+
+/// Check whether a src field of type `src_type` would be merged into a target field of type
+/// `target_type` via a conversion, which isn't supported for fallible merges yet.
+///
+/// Smart pointers and arrays with optional elements would otherwise be converted via `TryFrom`,
+/// contrary to all other merges. An error is returned for such types.
+fn unsupported_conversion(
+    src_type: &Type,
+    target_type: &Type,
+    context: TypeContext,
+) -> Option<TokenStream> {
+    if determine_pointer_conversion(src_type, target_type, context).is_some() {
+        return Some(err!(
+            src_type,
+            "Smart pointers aren't supported for fallible merges yet."
+        ));
+    }
+
+    if let (Type::Array(src_array), Type::Array(target_array)) = (src_type, target_type) {
+        let optional = |ty: &Type| {
+            matches!(
+                determine_field_type(ty.clone(), context.aliases),
+                FieldType::Optional { .. }
+            )
+        };
+        if optional(&src_array.elem) || optional(&target_array.elem) {
+            return Some(err!(
+                src_type,
+                "Arrays with optional elements aren't supported for fallible merges yet."
+            ));
+        }
+    }
+
+    None
+}
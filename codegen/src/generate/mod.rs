@@ -1,28 +1,31 @@
 use proc_macro2::TokenStream;
-use quote::ToTokens;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
-use syn::{Fields, GenericArgument, PathArguments, Type};
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    Field, Fields, GenericArgument, Generics, Ident, Index, Member, Path, PathArguments, Type,
+    TypePath, TypeReference,
+};
 
-use crate::{Mode, Parameters};
-
-macro_rules! equal_type_or_continue {
-    ($src_type:ident, $target_type:ident, $type:expr, $correct_macro:expr) => {
-        if !is_equal_type(&$src_type, &$target_type) {
-            err!(
-                $src_type,
-                "{}Type '{} cannot be merged into field of type '{}'.",
-                $type,
-                $src_type.to_token_stream(),
-                $target_type.to_token_stream()
-            )
-        } else {
-            $correct_macro
-        }
-    };
-}
+use crate::attributes::parse_field_options;
+use crate::imports::Imports;
+use crate::path::TargetOptions;
+use crate::{EnumParameters, Mode, Parameters};
 
 mod borrowed;
+mod enums;
+mod fallible;
 mod owned;
+mod partial;
+mod pointer;
+mod reverse;
+mod take;
+mod three_way;
+
+pub(crate) use partial::generate_partial;
+use pointer::{determine_pointer, determine_pointer_conversion, owned_type, PointerConversion};
+pub(crate) use reverse::reverse_parameters;
 
 /// Return a Tokenstream that contains all implementations to merge `src` into the `target`
 /// struct.
@@ -30,64 +33,1471 @@ mod owned;
 /// Known Limitations:
 /// - Error, when using different generic aliases that have same type.
 /// - Visibility of the `target` struct isn't taken into account.
-///     This will get better when module resolution is done properly.
+///   This will get better when module resolution is done properly.
 /// - Type equality cannot be properly ensured at this stage.
-///     Right now, we only check if the given tokens for a type are the same.
-///     However, it's extremely difficult to found out the actual type from a simple token.
+///   Right now, we resolve names via the `use` items of the src and target file and compare the
+///   resulting tokens. However, it's extremely difficult to found out the actual type from a
+///   simple token.
 ///
-///     The resulting code will still be correct though, as any type issues will be caught by the
-///     compiler anyway.
+///   The resulting code will still be correct though, as any type issues will be caught by the
+///   compiler anyway.
 /// - If people work with type aliases such as `type nice = Option<String>`, the `Option` detection
 ///   no longer works and thereby the `merge_soft*` functions won't work as expected.
 pub(crate) fn generate_impl(mode: &Mode, params: Parameters) -> Result<TokenStream, TokenStream> {
-    let target_fields = match params.target_struct.fields.clone() {
-        Fields::Named(fields) => fields,
-        _ => {
+    let mut errors = TokenStream::new();
+    let fields = match_fields(
+        &params.src_struct.fields,
+        &params.target_struct.fields,
+        &params.target_struct.ident,
+        &params.target_options,
+        &mut errors,
+    );
+
+    // Aliases of `Option` only break soft merges.
+    if params.options.deny_aliases && matches!(mode, Mode::Owned | Mode::Borrowed | Mode::Take) {
+        errors.extend(vec![deny_aliases(
+            &fields,
+            &params.generics,
+            &params.options.option_aliases,
+        )]);
+    }
+
+    // In the following, we'll generate all required functions for the `MergeInto` impl.
+    // Fields that cannot be merged are skipped and their errors are added to the output.
+    let tokens = match *mode {
+        Mode::Owned => owned::impl_owned(&params, fields),
+        Mode::Borrowed => borrowed::impl_borrowed(&params, fields),
+        Mode::Take => take::impl_take(&params, fields),
+        Mode::ThreeWay => three_way::impl_three_way(&params, fields),
+        Mode::Fallible => fallible::impl_fallible(&params, fields),
+    };
+
+    Ok(quote! {
+        #errors
+        #tokens
+    })
+}
+
+/// Return a Tokenstream that contains all implementations to merge the `src` into the `target`
+/// enum.
+///
+/// Only the owned and borrowed merges are supported for enums.
+pub(crate) fn generate_enum_impl(
+    mode: &Mode,
+    params: EnumParameters,
+) -> Result<TokenStream, TokenStream> {
+    let src_ident = &params.src_enum.ident;
+    match *mode {
+        Mode::Owned | Mode::Borrowed => {}
+        Mode::Take => {
+            return Err(err!(
+                src_ident,
+                "Take merges aren't supported for enums yet."
+            ))
+        }
+        Mode::ThreeWay => {
+            return Err(err!(
+                src_ident,
+                "Three-way merges aren't supported for enums yet."
+            ))
+        }
+        Mode::Fallible => {
             return Err(err!(
-                params.target_struct,
-                "struct_merge only works on structs with named fields."
+                src_ident,
+                "Fallible merges aren't supported for enums yet."
+            ))
+        }
+    }
+    if params.options.preview {
+        return Err(err!(
+            src_ident,
+            "Merge previews aren't supported for enums yet."
+        ));
+    }
+    if params.options.extract {
+        return Err(err!(src_ident, "Extraction isn't supported for enums yet."));
+    }
+    if params.options.diff {
+        return Err(err!(src_ident, "Diffs aren't supported for enums yet."));
+    }
+
+    Ok(enums::impl_enum(mode, &params))
+}
+
+/// A field of the src struct and the field of the target struct it's merged into.
+pub(crate) struct FieldMatch {
+    src: Field,
+    /// The member to access the src field. I.e. its name or its index.
+    src_member: Member,
+    target: Field,
+    /// The member to access the target field. I.e. its name or its index.
+    target_member: Member,
+}
+
+/// Get the members of all fields of a struct.
+fn members(fields: &Fields) -> Vec<(Member, Field)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(index)),
+            };
+            (member, field.clone())
+        })
+        .collect()
+}
+
+/// Find the respective target field for each src field.
+///
+/// - Fields of two structs with named fields are matched by their name.
+/// - Fields of two tuple structs are matched by their position.
+/// - Src fields can be mapped to a target field via `#[merge(name = "field")]` or
+///   `#[merge(index = 0)]`. This is required to merge tuple structs into structs with named
+///   fields and vice versa.
+///
+/// The options of the target take precedence:
+/// - Src fields in `skip(...)` aren't matched at all.
+/// - Src fields in `rename(src = target)` are mapped to the given target field.
+///
+/// Src fields without a respective target field are skipped, unless the target is `strict`.
+/// Explicitly mapped fields that cannot be found result in an error, which is added to `errors`.
+fn match_fields(
+    src_fields: &Fields,
+    target_fields: &Fields,
+    target_name: &impl ToTokens,
+    target_options: &TargetOptions,
+    errors: &mut TokenStream,
+) -> Vec<FieldMatch> {
+    let target_fields = members(target_fields);
+
+    let mut matches = Vec::new();
+    for (src_member, src) in members(src_fields) {
+        if target_options.skip.contains(&src_member) {
+            continue;
+        }
+
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&src.attrs).unwrap_or_default();
+        let renamed = target_options
+            .rename
+            .iter()
+            .find(|(from, _)| *from == src_member)
+            .map(|(_, to)| to.clone());
+
+        let explicit = renamed.is_some() || options.index.is_some() || options.name.is_some();
+        let target_member = if let Some(renamed) = renamed {
+            renamed
+        } else if let Some(index) = options.index {
+            Member::Unnamed(index)
+        } else if let Some(name) = options.name {
+            Member::Named(name)
+        } else {
+            src_member.clone()
+        };
+
+        let target = target_fields
+            .iter()
+            .find(|(member, _)| *member == target_member);
+        match target {
+            Some((_, target)) => matches.push(FieldMatch {
+                src,
+                src_member,
+                target: target.clone(),
+                target_member,
+            }),
+            None if explicit => errors.extend(vec![err!(
+                src,
+                "{} doesn't have a field '{}'.",
+                target_name.to_token_stream(),
+                target_member.to_token_stream()
+            )]),
+            None if target_options.strict => errors.extend(vec![err!(
+                src,
+                "{} doesn't have a field '{}'. Skip it via 'skip({})'.",
+                target_name.to_token_stream(),
+                target_member.to_token_stream(),
+                src_member.to_token_stream()
+            )]),
+            None => continue,
+        }
+    }
+
+    matches
+}
+
+/// Everything that's needed to compare the types of src and target fields.
+#[derive(Clone, Copy)]
+struct TypeContext<'a> {
+    /// The aliases of `Option`, which are declared via `#[merge(option_aliases(...))]`.
+    aliases: &'a [Ident],
+    src_imports: &'a Imports,
+    target_imports: &'a Imports,
+    /// Whether types that seem to differ are checked by the compiler instead of rejected.
+    defer_type_checks: bool,
+}
+
+impl Parameters {
+    fn type_context(&self) -> TypeContext<'_> {
+        TypeContext {
+            aliases: &self.options.option_aliases,
+            src_imports: &self.src_imports,
+            target_imports: &self.target_imports,
+            defer_type_checks: self.options.defer_type_checks,
+        }
+    }
+}
+
+impl EnumParameters {
+    fn type_context(&self) -> TypeContext<'_> {
+        TypeContext {
+            aliases: &self.options.option_aliases,
+            src_imports: &self.src_imports,
+            target_imports: &self.target_imports,
+            defer_type_checks: self.options.defer_type_checks,
+        }
+    }
+}
+
+/// Check whether two given [Type]s are of the same type.
+///
+/// Names are resolved via the imports of the src's and the target's file, so `Vec<String>` and
+/// `std::vec::Vec<String>` are the same type.
+/// Names that cannot be resolved, e.g. names of glob imports, are assumed to be the same as a
+/// path with the same last segment. Any type issues will be caught by the compiler anyway.
+fn is_equal_type(src_type: &Type, target_type: &Type, context: TypeContext) -> bool {
+    // The inner type of an `Option` alias is unknown and thereby assumed to be equal.
+    if matches!(src_type, Type::Infer(_)) || matches!(target_type, Type::Infer(_)) {
+        return true;
+    }
+
+    // Lifetimes of references are ignored, as they're checked by the compiler anyway.
+    let mut src_type = src_type.clone();
+    let mut target_type = target_type.clone();
+    StripLifetimes.visit_type_mut(&mut src_type);
+    StripLifetimes.visit_type_mut(&mut target_type);
+
+    context.src_imports.normalize(&mut src_type);
+    context.target_imports.normalize(&mut target_type);
+    if src_type.to_token_stream().to_string() == target_type.to_token_stream().to_string() {
+        return true;
+    }
+
+    if !has_unknown_names(&src_type) && !has_unknown_names(&target_type) {
+        return false;
+    }
+
+    StripPaths.visit_type_mut(&mut src_type);
+    StripPaths.visit_type_mut(&mut target_type);
+    src_type.to_token_stream().to_string() == target_type.to_token_stream().to_string()
+}
+
+/// Check whether a normalized type contains names, that couldn't be resolved.
+/// These are single-segment paths, which aren't primitives, and `self::` or `super::` paths,
+/// which couldn't be made absolute.
+fn has_unknown_names(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64", "Self",
+    ];
+
+    struct FindUnknown(bool);
+    impl VisitMut for FindUnknown {
+        fn visit_type_path_mut(&mut self, type_path: &mut TypePath) {
+            if let Some(segment) = type_path.path.segments.first() {
+                let relative = segment.ident == "self" || segment.ident == "super";
+                if type_path.qself.is_none()
+                    && (relative
+                        || type_path.path.segments.len() == 1
+                            && !PRIMITIVES
+                                .iter()
+                                .any(|primitive| segment.ident == primitive))
+                {
+                    self.0 = true;
+                }
+            }
+            visit_mut::visit_type_path_mut(self, type_path);
+        }
+    }
+
+    let mut finder = FindUnknown(false);
+    finder.visit_type_mut(&mut ty.clone());
+    finder.0
+}
+
+/// Reduces all paths in a type to their last segment, e.g. `crate::Config<T>` becomes
+/// `Config<T>`.
+struct StripPaths;
+
+impl VisitMut for StripPaths {
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        path.leading_colon = None;
+        if let Some(last) = path.segments.pop().map(|pair| pair.into_value()) {
+            path.segments.clear();
+            path.segments.push(last);
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+}
+
+/// Removes the lifetimes of all references in a type, e.g. `&'a str` becomes `&str`.
+struct StripLifetimes;
+
+impl VisitMut for StripLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        reference.lifetime = None;
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+}
+
+/// The different ways a value of a src field can be merged into its target field.
+#[derive(Clone, Copy)]
+enum Conversion {
+    /// Both fields have the same type.
+    /// `target = src`
+    Direct,
+    /// The target field is an `Option` of the src field's type.
+    /// `target = Some(src)`
+    Wrap,
+    /// The src field is an `Option` of the target field's type.
+    /// `if let Some(value) = src { target = value }`
+    Unwrap,
+}
+
+/// Describes how a src field can be merged into its target field.
+#[derive(Clone, Copy)]
+struct FieldConversion {
+    conversion: Conversion,
+    /// Whether the target field is an `Option`.
+    /// Only such fields are touched by the `merge_soft*` functions.
+    target_optional: bool,
+    /// Whether both fields are arrays, whose elements are merged one by one.
+    /// `conversion` and `target_optional` then describe the elements.
+    elements: bool,
+    /// The conversion of the src value, if either field is a smart pointer to the other's type.
+    pointer: Option<PointerConversion>,
+    /// Whether the src field is merged into the target field via its own merge implementation.
+    /// A `PointerConversion::Wrap` then describes the pointer of the target field.
+    /// Optional targets are only merged into, if the src field is a tri-state.
+    nested: bool,
+    /// Whether the src field is a `struct_merge::Patch`, which is merged like an
+    /// `Option<Option<T>>`.
+    patch: bool,
+}
+
+impl FieldMatch {
+    /// The name of the target field, as it's used in reports.
+    /// Fields of tuple structs are named by their index, raw identifiers without their `r#`.
+    fn name(&self) -> String {
+        member_name(&self.target_member)
+    }
+
+    /// Return an error, if the src or the target field is an unsized slice `[T]`.
+    ///
+    /// Such values can neither be moved nor cloned, so only references such as `&[T]` can be
+    /// merged.
+    fn slice_error(&self) -> Option<TokenStream> {
+        let slice = |ty: &Type| matches!(ty, Type::Slice(_));
+        if slice(&self.src.ty) {
+            return Some(err!(
+                self.src.ty,
+                "Slice fields aren't supported. Use a reference such as '&[T]' instead."
+            ));
+        }
+        if slice(&self.target.ty) {
+            return Some(err!(
+                self.src,
+                "The target field is a slice, which isn't supported. \
+                Use a reference such as '&[T]' instead."
             ));
         }
+
+        None
+    }
+
+    /// Determine the [FieldType]s of the src and the target field.
+    ///
+    /// `#[merge(optional)]`, `#[merge(required)]` and their `target_*` counterparts take
+    /// precedence over the detection via the field's type.
+    fn field_types(&self, aliases: &[Ident]) -> (FieldType, FieldType) {
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&self.src.attrs).unwrap_or_default();
+
+        (
+            override_field_type(
+                determine_field_type(self.src.ty.clone(), aliases),
+                options.optional,
+            ),
+            override_field_type(
+                determine_field_type(self.target.ty.clone(), aliases),
+                options.target_optional,
+            ),
+        )
+    }
+}
+
+/// Return errors for all fields, whose types might be aliases of `Option`.
+///
+/// This is the opt-in `#[merge(deny_aliases)]` lint, as such aliases silently break soft merges.
+/// Fields whose kind is explicitly set via `#[merge(optional)]` and the like are fine.
+fn deny_aliases(fields: &[FieldMatch], generics: &Generics, aliases: &[Ident]) -> TokenStream {
+    let mut errors = TokenStream::new();
+    for field in fields {
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&field.src.attrs).unwrap_or_default();
+
+        if options.optional.is_none() && is_ambiguous_type(&field.src.ty, generics, aliases) {
+            errors.extend(vec![err!(
+                field.src.ty,
+                "Type '{}' might be an alias of Option. \
+                Annotate the field with '#[merge(optional)]' or '#[merge(required)]'.",
+                field.src.ty.to_token_stream()
+            )]);
+        }
+
+        if options.target_optional.is_none()
+            && is_ambiguous_type(&field.target.ty, generics, aliases)
+        {
+            errors.extend(vec![err!(
+                field.src,
+                "The type '{}' of the target field might be an alias of Option. \
+                Annotate the field with '#[merge(target_optional)]' or '#[merge(target_required)]'.",
+                field.target.ty.to_token_stream()
+            )]);
+        }
+    }
+
+    errors
+}
+
+/// Check whether a type is a bare single-segment path without generic arguments, such as `Nice`,
+/// which might be an alias of `Option`.
+///
+/// Primitives, `String`, generic parameters and declared aliases are known and thereby fine.
+fn is_ambiguous_type(ty: &Type, generics: &Generics, aliases: &[Ident]) -> bool {
+    const KNOWN_TYPES: &[&str] = &[
+        "bool", "char", "str", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+        "i32", "i64", "i128", "isize", "f32", "f64", "Self",
+    ];
+
+    let path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return false,
     };
-    let src_fields = match params.src_struct.fields.clone() {
-        Fields::Named(fields) => fields,
-        _ => {
+    let ident = match path.get_ident() {
+        Some(ident) => ident,
+        None => return false,
+    };
+
+    !KNOWN_TYPES.iter().any(|known| ident == known)
+        && !aliases.contains(ident)
+        && !generics.type_params().any(|param| param.ident == *ident)
+}
+
+/// A pair of a src and a target field, which can be merged.
+#[derive(Clone)]
+struct FieldPair {
+    src_member: Member,
+    target_member: Member,
+    name: String,
+    conversion: FieldConversion,
+    /// How the value of the src field is obtained.
+    ref_access: RefAccess,
+    /// Assertions, which let the compiler check types that seem to differ.
+    /// See `#[merge(defer_type_checks)]`.
+    type_checks: TokenStream,
+}
+
+/// How the value of a src field is obtained from the src struct.
+#[derive(Clone)]
+enum RefAccess {
+    /// The value is moved or, if the src is borrowed, cloned.
+    Clone,
+    /// The value is copied. Set via `#[merge(copy)]`.
+    Copy,
+    /// The src holds a reference, whose value is converted via `ToOwned`, e.g. `&'a str` into
+    /// `String`. Set via `#[merge(to_owned)]`.
+    ToOwned,
+    /// The value is converted by a function, which takes a reference to it.
+    /// Set via `#[merge(ref_with = "path::to::fn")]`.
+    With(Path),
+}
+
+impl RefAccess {
+    /// Whether the value is converted, so it cannot be compared to the target's value or built
+    /// from it.
+    fn converts(&self) -> bool {
+        matches!(self, RefAccess::ToOwned | RefAccess::With(_))
+    }
+}
+
+/// Determine the conversions for all given pairs of fields.
+///
+/// Fields that cannot be merged are skipped and their errors are added to `errors`.
+fn determine_field_pairs(
+    fields: Vec<FieldMatch>,
+    context: TypeContext,
+    errors: &mut TokenStream,
+) -> Vec<FieldPair> {
+    let mut pairs = Vec::new();
+    for field in fields {
+        if let Some(error) = field.slice_error() {
+            errors.extend(vec![error]);
+            continue;
+        }
+        let name = field.name();
+        let (src_field_type, target_field_type) = field.field_types(context.aliases);
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&field.src.attrs).unwrap_or_default();
+        let nested = options.nested;
+        let ref_access = if options.copy {
+            RefAccess::Copy
+        } else if options.to_owned {
+            RefAccess::ToOwned
+        } else if let Some(function) = options.ref_with {
+            RefAccess::With(function)
+        } else {
+            RefAccess::Clone
+        };
+        let src_field_type = match ref_access_type(src_field_type, &ref_access, &field.src.ty) {
+            Ok(src_field_type) => src_field_type,
+            Err(error) => {
+                errors.extend(vec![error]);
+                continue;
+            }
+        };
+
+        // Patches are merged just like an `Option<Option<T>>`.
+        let patch = patch_inner(&field.src.ty, context);
+        let src_field_type = match &patch {
+            Some(inner) => FieldType::Optional {
+                inner: syn::parse_quote!(Option<#inner>),
+                outer: syn::parse_quote!(Option<Option<#inner>>),
+            },
+            None => src_field_type,
+        };
+
+        let mut type_checks = TokenStream::new();
+        let conversion = if nested {
+            determine_nested_conversion(src_field_type, target_field_type, context.aliases)
+        } else {
+            determine_conversion(src_field_type, target_field_type, context, &mut type_checks)
+        };
+        let conversion = match (conversion, patch) {
+            (Ok(Some(conversion)), Some(_)) => patch_conversion(conversion, &field.src.ty),
+            (conversion, _) => conversion,
+        };
+        let conversion = match conversion {
+            Ok(Some(conversion))
+                if !matches!(ref_access, RefAccess::Clone)
+                    && (conversion.nested
+                        || conversion.patch
+                        || conversion.elements
+                        || conversion.pointer.is_some()) =>
+            {
+                Err(err!(
+                    field.src.ty,
+                    "'copy', 'to_owned' and 'ref_with' aren't supported for nested, patch, \
+                    array and smart pointer fields."
+                ))
+            }
+            conversion => conversion,
+        };
+        match conversion {
+            Ok(Some(conversion)) => pairs.push(FieldPair {
+                src_member: field.src_member,
+                target_member: field.target_member,
+                name,
+                conversion,
+                ref_access,
+                type_checks,
+            }),
+            Ok(None) => continue,
+            Err(error) => errors.extend(vec![error]),
+        }
+    }
+
+    pairs
+}
+
+/// Get the type of a src field, as it's compared to the target field's type.
+///
+/// `#[merge(to_owned)]` fields must be references and are compared via their owned type, e.g.
+/// `&'a str` as `String`. The return type of `#[merge(ref_with = "fn")]` is unknown and thereby
+/// assumed to be equal to the target's type.
+fn ref_access_type(
+    field_type: FieldType,
+    ref_access: &RefAccess,
+    ty: &Type,
+) -> Result<FieldType, TokenStream> {
+    let convert = |inner: Type| -> Result<Type, TokenStream> {
+        match (ref_access, inner) {
+            (RefAccess::ToOwned, Type::Reference(reference)) => Ok(owned_type(*reference.elem)),
+            (RefAccess::ToOwned, _) => Err(err!(
+                ty,
+                "Fields with '#[merge(to_owned)]' must be references, e.g. &'a str."
+            )),
+            (RefAccess::With(_), _) => Ok(Type::Infer(syn::parse_quote!(_))),
+            (_, inner) => Ok(inner),
+        }
+    };
+
+    Ok(match field_type {
+        FieldType::Normal(inner) => FieldType::Normal(convert(inner)?),
+        FieldType::Optional { inner, .. } if ref_access.converts() => {
+            let inner = convert(inner)?;
+            FieldType::Optional {
+                outer: syn::parse_quote!(Option<#inner>),
+                inner,
+            }
+        }
+        field_type => field_type,
+    })
+}
+
+/// Remove all pairs, whose src values are converted via `#[merge(to_owned)]` or
+/// `#[merge(ref_with = "fn")]`, and add an error for each to `errors`.
+///
+/// `kind` names the merges that don't support conversions yet, e.g. "three-way merges".
+fn reject_conversions(
+    pairs: Vec<FieldPair>,
+    kind: &str,
+    errors: &mut TokenStream,
+) -> Vec<FieldPair> {
+    pairs
+        .into_iter()
+        .filter(|pair| {
+            if pair.ref_access.converts() {
+                errors.extend(vec![err!(
+                    pair.src_member,
+                    "'to_owned' and 'ref_with' aren't supported for {} yet.",
+                    kind
+                )]);
+            }
+            !pair.ref_access.converts()
+        })
+        .collect()
+}
+
+/// Determine how a src field of type `src_type` can be merged into a target field of type
+/// `target_type`.
+///
+/// `Ok(None)` is returned, if either of the types is invalid. Such fields are simply skipped.
+/// If the types cannot be merged, an error is returned.
+/// With `#[merge(defer_type_checks)]`, the types are merged anyway and an assertion is added to
+/// `type_checks`, so the compiler reports actual mismatches at the src field.
+///
+/// Arrays with optional elements are merged element-wise, e.g. `[Option<T>; N]` into `[T; N]`.
+fn determine_conversion(
+    src_field_type: FieldType,
+    target_field_type: FieldType,
+    context: TypeContext,
+    type_checks: &mut TokenStream,
+) -> Result<Option<FieldConversion>, TokenStream> {
+    if let (
+        FieldType::Normal(Type::Array(src_array)),
+        FieldType::Normal(Type::Array(target_array)),
+    ) = (&src_field_type, &target_field_type)
+    {
+        let src_element = determine_field_type(*src_array.elem.clone(), context.aliases);
+        let target_element = determine_field_type(*target_array.elem.clone(), context.aliases);
+        if matches!(src_element, FieldType::Optional { .. })
+            || matches!(target_element, FieldType::Optional { .. })
+        {
+            if src_array.len.to_token_stream().to_string()
+                != target_array.len.to_token_stream().to_string()
+            {
+                return Err(err!(
+                    src_array,
+                    "Array of length '{}' cannot be merged into array of length '{}'.",
+                    src_array.len.to_token_stream(),
+                    target_array.len.to_token_stream()
+                ));
+            }
+
+            let conversion =
+                determine_conversion(src_element, target_element, context, type_checks)?;
+            return Ok(conversion.map(|conversion| FieldConversion {
+                elements: true,
+                ..conversion
+            }));
+        }
+    }
+
+    let (conversion, target_optional, src_type, target_type, prefix) =
+        match (src_field_type, target_field_type) {
+            // Both fields have the same type
+            (FieldType::Normal(src_type), FieldType::Normal(target_type)) => {
+                (Conversion::Direct, false, src_type, target_type, "")
+            }
+            // The src is optional and needs to be `Some(T)` to be merged.
+            (FieldType::Optional { inner, .. }, FieldType::Normal(target_type)) => {
+                (Conversion::Unwrap, false, inner, target_type, "Inner ")
+            }
+            // The target is optional and needs to be wrapped in `Some(T)` to be merged.
+            (FieldType::Normal(src_type), FieldType::Optional { inner, .. }) => {
+                (Conversion::Wrap, true, src_type, inner, "")
+            }
+            // Both fields are optional. It can now be either of these:
+            // - (Option<T>, Option<T>)
+            // - (Option<Option<T>>, Option<T>)
+            // - (Option<T>, Option<Option<T>>)
+            (
+                FieldType::Optional {
+                    inner: inner_src_type,
+                    outer: outer_src_type,
+                },
+                FieldType::Optional {
+                    inner: inner_target_type,
+                    outer: outer_target_type,
+                },
+            ) => {
+                if is_equal_type(&inner_src_type, &inner_target_type, context)
+                    || determine_pointer_conversion(&inner_src_type, &inner_target_type, context)
+                        .is_some()
+                {
+                    (
+                        Conversion::Direct,
+                        true,
+                        inner_src_type,
+                        inner_target_type,
+                        "",
+                    )
+                } else if is_equal_type(&inner_src_type, &outer_target_type, context) {
+                    (
+                        Conversion::Unwrap,
+                        true,
+                        inner_src_type,
+                        outer_target_type,
+                        "",
+                    )
+                } else {
+                    (
+                        Conversion::Wrap,
+                        true,
+                        outer_src_type,
+                        inner_target_type,
+                        "",
+                    )
+                }
+            }
+            // Skip anything where either of the fields are invalid
+            (FieldType::Invalid, _) | (_, FieldType::Invalid) => return Ok(None),
+        };
+
+    // Smart pointers are wrapped and unwrapped, if the types differ.
+    let pointer = if is_equal_type(&src_type, &target_type, context) {
+        None
+    } else if let Some(pointer) = determine_pointer_conversion(&src_type, &target_type, context) {
+        Some(pointer)
+    } else if context.defer_type_checks {
+        type_checks.extend(vec![assert_same_type(&src_type, &target_type, context)]);
+        None
+    } else {
+        return Err(err!(
+            src_type,
+            "{}Type '{} cannot be merged into field of type '{}'.",
+            prefix,
+            src_type.to_token_stream(),
+            target_type.to_token_stream()
+        ));
+    };
+
+    Ok(Some(FieldConversion {
+        conversion,
+        target_optional,
+        elements: false,
+        pointer,
+        nested: false,
+        patch: false,
+    }))
+}
+
+/// Generate an assertion, which fails to compile if the src and the target type differ.
+/// The error points at the src field's type.
+///
+/// The target type is written in the src's file, so its names are resolved first.
+fn assert_same_type(src_type: &Type, target_type: &Type, context: TypeContext) -> TokenStream {
+    let mut target_type = target_type.clone();
+    context.target_imports.normalize(&mut target_type);
+
+    quote_spanned! {src_type.span()=>
+        let _: std::marker::PhantomData<#target_type> = std::marker::PhantomData::<#src_type>;
+    }
+}
+
+/// Determine how a src field is merged into its target field via `#[merge(nested)]`.
+///
+/// The src field's type must implement the respective merge trait for the target field's type.
+/// Optional src fields are only merged, if they're `Some`.
+/// Targets behind a smart pointer are merged via a mutable reference to their value.
+///
+/// Optional targets can only be merged into by tri-state src fields, i.e. `Option<Option<T>>`
+/// or `Patch<T>`. These clear the target or are merged into its value, if it exists.
+fn determine_nested_conversion(
+    src_field_type: FieldType,
+    target_field_type: FieldType,
+    aliases: &[Ident],
+) -> Result<Option<FieldConversion>, TokenStream> {
+    let (conversion, tri_state) = match src_field_type {
+        FieldType::Normal(_) => (Conversion::Direct, false),
+        FieldType::Optional { inner, .. } => (
+            Conversion::Unwrap,
+            matches!(
+                determine_field_type(inner, aliases),
+                FieldType::Optional { .. }
+            ),
+        ),
+        FieldType::Invalid => return Ok(None),
+    };
+
+    let (pointer, target_optional) = match target_field_type {
+        FieldType::Normal(target_type) => (determine_pointer(&target_type), false),
+        FieldType::Optional { inner, .. } if tri_state => (determine_pointer(&inner), true),
+        FieldType::Optional { outer, .. } => {
             return Err(err!(
-                params.target_struct,
-                "struct_merge only works on structs with named fields."
-            ));
+                outer,
+                "Nested merges into optional fields are only supported for Option<Option<T>> \
+                and Patch<T> fields."
+            ))
+        }
+        FieldType::Invalid => return Ok(None),
+    };
+    let pointer = pointer.map(|(pointer, _)| PointerConversion::Wrap(pointer));
+
+    Ok(Some(FieldConversion {
+        conversion,
+        target_optional,
+        elements: false,
+        pointer,
+        nested: true,
+        patch: false,
+    }))
+}
+
+/// Get the value type `T` of a `struct_merge::Patch<T>` field.
+///
+/// `Patch` is resolved via the imports of the src's file, so other types with the same name
+/// aren't mistaken for it.
+fn patch_inner(ty: &Type, context: TypeContext) -> Option<Type> {
+    let mut normalized = ty.clone();
+    context.src_imports.normalize(&mut normalized);
+    let is_patch = match &normalized {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let segments: Vec<String> = type_path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            matches!(
+                segments
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice(),
+                ["Patch"] | ["struct_merge", "Patch"]
+            )
+        }
+        _ => false,
+    };
+    if !is_patch {
+        return None;
+    }
+
+    let segment = match ty {
+        Type::Path(type_path) => type_path.path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(params) => params.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(inner) => Some(inner.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Mark the conversion of a `Patch<T>` field, which has been determined as if it were an
+/// `Option<Option<T>>`.
+///
+/// Patches can only be merged into `Option<T>` fields, as the target must be clearable.
+fn patch_conversion(
+    conversion: FieldConversion,
+    src_type: &Type,
+) -> Result<Option<FieldConversion>, TokenStream> {
+    if !matches!(conversion.conversion, Conversion::Unwrap) || !conversion.target_optional {
+        return Err(err!(
+            src_type,
+            "Patch fields can only be merged into fields of type Option<T>."
+        ));
+    }
+
+    Ok(Some(FieldConversion {
+        patch: true,
+        ..conversion
+    }))
+}
+
+/// Access the tri-state of a `Patch<T>` src field as an `Option<Option<T>>`.
+fn patch_access(src: &SrcAccess) -> SrcAccess {
+    let value = &src.value;
+    let value = quote!(struct_merge::Patch::into_option(#value));
+    match &src.reference {
+        None => SrcAccess {
+            value: value.clone(),
+            option: value,
+            inner: quote!(value),
+            reference: None,
+        },
+        Some(reference) => SrcAccess {
+            value,
+            option: quote!(struct_merge::Patch::as_option(#reference)),
+            inner: quote!(value.cloned()),
+            reference: Some(reference.clone()),
+        },
+    }
+}
+
+/// The expressions that are used to access the value of a src field.
+///
+/// These differ, depending on whether the src is owned or borrowed.
+struct SrcAccess {
+    /// The value of the src field.
+    /// E.g. `self.field` or `self.field.clone()`.
+    value: TokenStream,
+    /// The src field as an `Option`, which can be matched against.
+    /// E.g. `self.field` or `self.field.as_ref()`.
+    option: TokenStream,
+    /// The inner value of `option`, which is bound to the `value` identifier.
+    /// E.g. `value` or `value.clone()`.
+    inner: TokenStream,
+    /// A reference to the src field, if the src is borrowed.
+    /// E.g. `&self.field`. Nested merges then use `StructMergeIntoRef`.
+    reference: Option<TokenStream>,
+}
+
+/// The name of a member, as it's used in reports.
+/// Unnamed members are named by their index, raw identifiers without their `r#`.
+fn member_name(member: &Member) -> String {
+    match member {
+        Member::Named(ident) => ident.unraw().to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+/// The identifier of a local binding for the value of a member, e.g. `converted_name` or
+/// `converted_0`. Raw identifiers such as `r#type` are bound without their prefix.
+///
+/// The binding isn't spanned at the member, so lints don't point at the user's code.
+fn member_binding(prefix: &str, member: &Member) -> Ident {
+    match member {
+        Member::Named(ident) => format_ident!(
+            "{}_{}",
+            prefix,
+            ident.unraw(),
+            span = proc_macro2::Span::call_site()
+        ),
+        Member::Unnamed(index) => format_ident!("{}_{}", prefix, index.index),
+    }
+}
+
+/// Apply the `copy`, `to_owned` or `ref_with` option of a src field to its default access.
+///
+/// The src field is accessed via `field`, e.g. `self.field`.
+/// Converted values are obtained from a reference to the field, so it's neither moved nor
+/// cloned.
+fn apply_ref_access(pair: &FieldPair, src: SrcAccess, field: &TokenStream) -> SrcAccess {
+    // Whether the value is merged as a whole, even though the src is optional.
+    let conversion = &pair.conversion;
+    let optional = match conversion.conversion {
+        Conversion::Direct => conversion.target_optional,
+        Conversion::Unwrap => true,
+        Conversion::Wrap => false,
+    };
+
+    let (value, option, inner) = match &pair.ref_access {
+        RefAccess::Clone => return src,
+        RefAccess::Copy => (field.clone(), field.clone(), quote!(value)),
+        RefAccess::ToOwned => {
+            let value = if optional {
+                quote!(#field.map(std::borrow::ToOwned::to_owned))
+            } else {
+                quote!(std::borrow::ToOwned::to_owned(#field))
+            };
+            (
+                value,
+                field.clone(),
+                quote!(std::borrow::ToOwned::to_owned(value)),
+            )
+        }
+        RefAccess::With(function) => {
+            let value = if optional {
+                quote!(#field.as_ref().map(#function))
+            } else {
+                quote!(#function(&#field))
+            };
+            (value, quote!(#field.as_ref()), quote!(#function(value)))
+        }
+    };
+
+    SrcAccess {
+        value,
+        option,
+        inner,
+        reference: src.reference,
+    }
+}
+
+/// Determines which kind of merge function is generated.
+#[derive(Clone, Copy)]
+struct MergeFlags {
+    /// Only merge into target fields that are `None`.
+    soft: bool,
+    /// Record all written and skipped fields in a `report` variable.
+    report: bool,
+    /// Also record all fields whose values changed in the `report`.
+    track_changes: bool,
+}
+
+/// Generate the code that merges a single src field into the `target` field expression.
+///
+/// `None` is returned, if there's nothing to be done for this field.
+fn merge_field(
+    pair: &FieldPair,
+    src: &SrcAccess,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> Option<TokenStream> {
+    let snippet = merge_field_value(pair, src, target, flags)?;
+    let type_checks = &pair.type_checks;
+
+    Some(quote! {
+        #type_checks
+        #snippet
+    })
+}
+
+/// Generate the merge code of [merge_field] without the type checks of the field.
+fn merge_field_value(
+    pair: &FieldPair,
+    src: &SrcAccess,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> Option<TokenStream> {
+    let patched;
+    let src = if pair.conversion.patch {
+        patched = patch_access(src);
+        &patched
+    } else {
+        src
+    };
+
+    if pair.conversion.elements {
+        return merge_elements(pair, &src.value, target, flags);
+    }
+    if pair.conversion.nested {
+        return Some(merge_nested(pair, src, target, flags));
+    }
+
+    let name = &pair.name;
+    let conversion = &pair.conversion;
+
+    // The code to assign a new value to the target field.
+    let assign = |value: TokenStream| -> TokenStream {
+        if !flags.report {
+            return quote! {
+                #target = #value;
+            };
+        }
+
+        let track_changes = if flags.track_changes {
+            quote! {
+                if #target != merged {
+                    report.changed.push(#name);
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
+
+        quote! {
+            let merged = #value;
+            #track_changes
+            #target = merged;
+            report.written.push(#name);
+        }
+    };
+
+    // The code that's executed if a field is skipped.
+    let skip = if flags.report {
+        quote! {
+            else {
+                report.skipped.push(#name);
+            }
         }
+    } else {
+        TokenStream::new()
     };
 
-    let mut similar_fields = Vec::new();
-    for src_field in src_fields.named {
-        for target_field in target_fields.named.clone() {
-            if src_field.ident == target_field.ident {
-                similar_fields.push((src_field.clone(), target_field));
+    let SrcAccess {
+        value,
+        option,
+        inner,
+        ..
+    } = src;
+
+    let value = &convert_value(conversion, value, conversion.target_optional);
+    let inner = &convert_value(conversion, inner, conversion.target_optional);
+
+    if !flags.soft {
+        let snippet = match conversion.conversion {
+            Conversion::Direct => assign(value.clone()),
+            Conversion::Wrap => assign(quote!(Some(#value))),
+            Conversion::Unwrap => {
+                let assign = assign(inner.clone());
+                quote! {
+                    if let Some(value) = #option {
+                        #assign
+                    } #skip
+                }
             }
+        };
+
+        return Some(snippet);
+    }
+
+    // Soft merge only applies if the target field is `Optional`.
+    if !conversion.target_optional {
+        if flags.report {
+            return Some(quote! {
+                report.skipped.push(#name);
+            });
         }
+        return None;
     }
 
-    // In the following, we'll generate all required functions for the `MergeInto` impl.
-    // If any of the functions fails to be generated, we skip the impl for this struct.
-    // The errors will be generated in the individual token generator functions.
-    match *mode {
-        Mode::Owned => Ok(owned::impl_owned(&params, similar_fields)),
-        Mode::Borrowed => Ok(borrowed::impl_borrowed(&params, similar_fields)),
+    let snippet = match conversion.conversion {
+        Conversion::Direct | Conversion::Wrap => {
+            let value = match conversion.conversion {
+                Conversion::Wrap => quote!(Some(#value)),
+                _ => value.clone(),
+            };
+            let assign = assign(value);
+            quote! {
+                if #target.is_none() {
+                    #assign
+                } #skip
+            }
+        }
+        // The target is checked first, so values that aren't merged aren't taken from the src.
+        Conversion::Unwrap => {
+            let assign = assign(inner.clone());
+            quote! {
+                if #target.is_none() {
+                    if let Some(value) = #option {
+                        #assign
+                    } #skip
+                } #skip
+            }
+        }
+    };
+
+    Some(snippet)
+}
+
+/// Apply an explicit `#[merge(optional)]` or `#[merge(required)]` to a detected [FieldType].
+///
+/// The inner type of explicitly optional fields is unknown, if it isn't detected.
+fn override_field_type(field_type: FieldType, optional: Option<bool>) -> FieldType {
+    match (field_type, optional) {
+        (FieldType::Normal(ty), Some(true)) => FieldType::Optional {
+            inner: Type::Infer(syn::parse_quote!(_)),
+            outer: ty,
+        },
+        (FieldType::Optional { outer, .. }, Some(false)) => FieldType::Normal(outer),
+        (field_type, _) => field_type,
     }
 }
 
-/// Check whether two given [Type]s are of the same type.
-/// If they aren't, an error is added to the src_type and the function returns `false`.
+/// Check whether the path is `Option`, `std::option::Option` or `core::option::Option`.
+/// Generic arguments are ignored.
+fn is_option_path(path: &syn::Path) -> bool {
+    let segments: Vec<String> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+
+    match segments.as_slice() {
+        [option] => path.leading_colon.is_none() && option == "Option",
+        [krate, module, option] => {
+            (krate == "std" || krate == "core") && module == "option" && option == "Option"
+        }
+        _ => false,
+    }
+}
+
+/// Apply the pointer conversion of a field to a merged value.
+/// `optional` values are converted via `map`.
+fn convert_value(conversion: &FieldConversion, value: &TokenStream, optional: bool) -> TokenStream {
+    match conversion.pointer {
+        None => value.clone(),
+        Some(pointer) if optional => {
+            let converted = pointer.apply(&quote!(value));
+            quote!(#value.map(|value| #converted))
+        }
+        Some(pointer) => pointer.apply(value),
+    }
+}
+
+/// Generate the code that merges a single src field into the `target` field expression via the
+/// src field's own merge implementation.
 ///
-/// This check is rather crude, as we simply compare the token streams.
-/// However, this is the only way for now, as there are no type infos at this stage.
-fn is_equal_type(src_type: &Type, target_type: &Type) -> bool {
-    if src_type.to_token_stream().to_string() != target_type.to_token_stream().to_string() {
-        return false;
+/// In the `merge_*report` functions, the field is reported as written or changed, if any of its
+/// nested fields has been written or changed.
+fn merge_nested(
+    pair: &FieldPair,
+    src: &SrcAccess,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> TokenStream {
+    let name = &pair.name;
+
+    if pair.conversion.target_optional {
+        return merge_nested_tri_state(pair, src, target, flags);
+    }
+
+    // Targets behind a smart pointer are merged via a mutable reference to their value.
+    let target = match pair.conversion.pointer {
+        Some(PointerConversion::Wrap(pointer)) => pointer.as_mut(target),
+        _ => quote!(&mut #target),
+    };
+
+    // Borrowed src fields are merged by reference.
+    let (trait_name, function) = match src.reference {
+        Some(_) => ("StructMergeIntoRef", "merge_into_ref"),
+        None => ("StructMergeInto", "merge_into"),
+    };
+    let trait_name = Ident::new(trait_name, proc_macro2::Span::call_site());
+    let function = format_ident!(
+        "{}{}{}",
+        function,
+        if flags.soft { "_soft" } else { "" },
+        if flags.report { "_report" } else { "" }
+    );
+    let merge = |value: &TokenStream| -> TokenStream {
+        if !flags.report {
+            return quote! {
+                struct_merge::#trait_name::#function(#value, #target);
+            };
+        }
+
+        let track_changes = if flags.track_changes {
+            quote! {
+                if nested.is_changed() {
+                    report.changed.push(#name);
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
+        quote! {
+            let nested = struct_merge::#trait_name::#function(#value, #target);
+            if nested.is_written() {
+                report.written.push(#name);
+            } else {
+                report.skipped.push(#name);
+            }
+            #track_changes
+        }
+    };
+
+    match pair.conversion.conversion {
+        Conversion::Unwrap => {
+            let merge = merge(&quote!(value));
+            let option = &src.option;
+            let skip = if flags.report {
+                quote! {
+                    else {
+                        report.skipped.push(#name);
+                    }
+                }
+            } else {
+                TokenStream::new()
+            };
+            quote! {
+                if let Some(value) = #option {
+                    #merge
+                } #skip
+            }
+        }
+        _ => merge(src.reference.as_ref().unwrap_or(&src.value)),
+    }
+}
+
+/// Generate the code that merges a tri-state src field into an optional `target` via
+/// `#[merge(nested)]`.
+///
+/// - `None` leaves the target untouched.
+/// - `Some(None)` clears the target. Soft merges skip this, unless the target already is `None`.
+/// - `Some(Some(value))` is merged into the target's value. It's skipped, if the target is `None`,
+///   as there's nothing to merge into.
+fn merge_nested_tri_state(
+    pair: &FieldPair,
+    src: &SrcAccess,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> TokenStream {
+    let name = &pair.name;
+
+    // The value is merged just like a required nested field.
+    let value_pair = FieldPair {
+        conversion: FieldConversion {
+            conversion: Conversion::Direct,
+            target_optional: false,
+            ..pair.conversion
+        },
+        ..pair.clone()
+    };
+    let value_src = SrcAccess {
+        value: quote!(value),
+        option: quote!(value),
+        inner: quote!(value),
+        reference: src.reference.as_ref().map(|_| quote!(value)),
+    };
+    let merge = merge_nested(&value_pair, &value_src, &quote!((*target_value)), flags);
+
+    let skip = if flags.report {
+        quote! {
+            else {
+                report.skipped.push(#name);
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    let written = if flags.report {
+        quote!(report.written.push(#name);)
+    } else {
+        TokenStream::new()
+    };
+    let changed = if flags.track_changes {
+        quote! {
+            if #target.is_some() {
+                report.changed.push(#name);
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    let clear = if flags.soft {
+        quote! {
+            if #target.is_none() {
+                #written
+            } #skip
+        }
+    } else {
+        quote! {
+            #changed
+            #target = None;
+            #written
+        }
+    };
+
+    let option = &src.option;
+    quote! {
+        if let Some(value) = #option {
+            match value {
+                Some(value) => {
+                    if let Some(target_value) = &mut #target {
+                        #merge
+                    } #skip
+                }
+                None => {
+                    #clear
+                }
+            }
+        } #skip
+    }
+}
+
+/// Generate the code that merges the elements of the `src_value` array into the elements of the
+/// `target` array expression.
+///
+/// In the `merge_*report` functions, the field is reported as written or changed, if any of its
+/// elements has been written or changed.
+fn merge_elements(
+    pair: &FieldPair,
+    src_value: &TokenStream,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> Option<TokenStream> {
+    let merge_loop = merge_elements_loop(pair, src_value, target, flags)?;
+    if !flags.report {
+        return Some(merge_loop);
     }
 
-    true
+    // The elements are recorded in their own report, which shadows the actual report.
+    let name = &pair.name;
+    let track_changes = if flags.track_changes {
+        quote! {
+            if elements.is_changed() {
+                report.changed.push(#name);
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    Some(quote! {
+        {
+            let mut elements = struct_merge::MergeReport::default();
+            {
+                let report = &mut elements;
+                #merge_loop
+            }
+            if elements.is_written() {
+                report.written.push(#name);
+            } else {
+                report.skipped.push(#name);
+            }
+            #track_changes
+        }
+    })
+}
+
+/// Generate the loop, that merges each element of the `src_value` array into the respective
+/// element of the `target` array expression.
+fn merge_elements_loop(
+    pair: &FieldPair,
+    src_value: &TokenStream,
+    target: &TokenStream,
+    flags: MergeFlags,
+) -> Option<TokenStream> {
+    let element_pair = FieldPair {
+        conversion: FieldConversion {
+            elements: false,
+            ..pair.conversion
+        },
+        // The types are already checked for the whole array.
+        type_checks: TokenStream::new(),
+        ..pair.clone()
+    };
+    // The elements of the src array are owned values.
+    let src = SrcAccess {
+        value: quote!(src_element),
+        option: quote!(src_element),
+        inner: quote!(value),
+        reference: None,
+    };
+    let snippet = merge_field(&element_pair, &src, &quote!((*target_element)), flags)?;
+
+    Some(quote! {
+        for (target_element, src_element) in #target.iter_mut().zip(#src_value) {
+            #snippet
+        }
+    })
 }
 
 /// Internal representation of parsed types
@@ -105,11 +1515,15 @@ enum FieldType {
 /// This detected variant is represented via the [FieldType] enum.
 /// Invalid or unsupported types return the `FieldType::Invalid` variant.
 ///
+/// Besides `Option<T>`, the fully qualified `std::option::Option<T>` and `core::option::Option<T>`
+/// paths are detected, as well as the given `aliases` of `Option`.
+///
 /// Known limitations:
 ///
-/// This doesn't work with type aliases. We literally check the tokens for `Option<...>`.
+/// Type aliases only work, if they're declared via `#[merge(option_aliases(...))]`.
+/// We literally check the tokens for `Option<...>`.
 /// If there's an optional type that doesn't look like this, we won't detect it.
-fn determine_field_type(ty: Type) -> FieldType {
+fn determine_field_type(ty: Type, aliases: &[Ident]) -> FieldType {
     match ty.clone() {
         Type::Path(type_path) => {
             // The path is relative to `Self` and thereby non-optional
@@ -119,20 +1533,35 @@ fn determine_field_type(ty: Type) -> FieldType {
 
             let path = type_path.path;
 
-            // `Option<T>` shouldn't have a leading colon or multiple segments.
-            if path.leading_colon.is_some() || path.segments.len() > 1 {
-                return FieldType::Normal(ty);
-            }
-
             // The path should have at least one segment.
-            let segment = if let Some(segment) = path.segments.iter().next() {
+            let segment = if let Some(segment) = path.segments.last() {
                 segment
             } else {
                 return FieldType::Normal(ty);
             };
 
-            // The segment isn't an option.
-            if segment.ident != "Option" {
+            // Aliases may hide the inner type, e.g. `type MaybeString = Option<String>`.
+            // In that case, the inner type is unknown and represented by `_`.
+            if path.leading_colon.is_none()
+                && path.segments.len() == 1
+                && aliases.contains(&segment.ident)
+            {
+                let inner = match &segment.arguments {
+                    PathArguments::AngleBracketed(params) => {
+                        params.args.iter().find_map(|arg| match arg {
+                            GenericArgument::Type(inner) => Some(inner.clone()),
+                            _ => None,
+                        })
+                    }
+                    _ => None,
+                };
+                return FieldType::Optional {
+                    inner: inner.unwrap_or_else(|| Type::Infer(syn::parse_quote!(_))),
+                    outer: ty,
+                };
+            }
+
+            if !is_option_path(&path) {
                 return FieldType::Normal(ty);
             }
 
@@ -167,10 +1596,19 @@ fn determine_field_type(ty: Type) -> FieldType {
                 }
             }
         }
+        // These types are merged as a whole.
+        // Arrays with optional elements are handled in [determine_conversion].
+        // Slices are rejected by [FieldMatch::slice_error].
+        Type::Reference(_)
+        | Type::Array(_)
+        | Type::Tuple(_)
+        | Type::Paren(_)
+        | Type::Ptr(_)
+        | Type::BareFn(_) => FieldType::Normal(ty),
         _ => {
             err!(
                 ty,
-                "Found a non-path type. This isn't supported in struct-merge yet."
+                "Found an unsupported type. This isn't supported in struct-merge yet."
             );
             FieldType::Invalid
         }
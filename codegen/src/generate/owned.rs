@@ -1,230 +1,135 @@
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use syn::Field;
+use quote::quote;
 
 use super::*;
 
 /// Generate the implementation of [struct_merge::StructMerge] for given structs.
-pub(crate) fn impl_owned(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
+pub(crate) fn impl_owned(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    let mut errors = TokenStream::new();
+    let pairs = determine_field_pairs(fields, params.type_context(), &mut errors);
+
     let mut functions_tokens = TokenStream::new();
 
-    let stream = merge(params, fields.clone());
+    let stream = merge(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_soft(params, &pairs);
     functions_tokens.extend(vec![stream]);
 
-    let stream = merge_soft(params, fields);
+    let stream = merge_report(params, &pairs);
     functions_tokens.extend(vec![stream]);
 
-    let src_ident = &params.src_struct.ident;
+    let stream = merge_soft_report(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
     let target_path = &params.target_path;
     quote! {
-        impl struct_merge::StructMergeInto<#target_path> for #src_ident {
+        #errors
+
+        impl #impl_generics struct_merge::StructMergeInto<#target_path> for #src_type #where_clause {
             #functions_tokens
         }
     }
 }
 
-/// Generate the [struct_merge::StructMerge::merge] function for the given structs.
-fn merge(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
+/// Generate the code that merges all fields of `self` into `target`.
+fn merge_code(pairs: &[FieldPair], flags: MergeFlags) -> TokenStream {
     let mut merge_code = TokenStream::new();
-    for (src_field, dest_field) in fields {
-        let src_ident = src_field.ident;
-        let dest_ident = dest_field.ident;
-
-        // Find out, whether the fields are optional or not.
-        let src_field_type = determine_field_type(src_field.ty);
-        let dest_field_type = determine_field_type(dest_field.ty);
-
-        let snippet = match (src_field_type, dest_field_type) {
-            // Both fields have the same type
-            (FieldType::Normal(src_type), FieldType::Normal(dest_type)) => {
-                equal_type_or_continue!(
-                    src_type,
-                    dest_type,
-                    "",
-                    quote! {
-                        dest.#dest_ident = self.#src_ident;
-                    }
-                )
-            }
-            // The src is optional and needs to be `Some(T)` to be merged.
-            (
-                FieldType::Optional {
-                    inner: src_type, ..
-                },
-                FieldType::Normal(dest_type),
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    dest_type,
-                    "Inner",
-                    quote! {
-                        if let Some(value) = self.#src_ident {
-                            dest.#dest_ident = value;
-                        }
-                    }
-                )
-            }
-            // The dest is optional and needs to be wrapped in `Some(T)` to be merged.
-            (
-                FieldType::Normal(src_type),
-                FieldType::Optional {
-                    inner: dest_type, ..
-                },
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    dest_type,
-                    "",
-                    quote! {
-                        dest.#dest_ident = Some(self.#src_ident);
-                    }
-                )
-            }
-            // Both fields are optional. It can now be either of these:
-            // - (Option<T>, Option<T>)
-            // - (Option<Option<T>>, Option<T>)
-            // - (Option<T>, Option<Option<T>>)
-            (
-                FieldType::Optional {
-                    inner: inner_src_type,
-                    outer: outer_src_type,
-                },
-                FieldType::Optional {
-                    inner: inner_dest_type,
-                    outer: outer_dest_type,
-                },
-            ) => {
-                // Handling the (Option<T>, Option<T>) case
-                if is_equal_type(&inner_src_type, &inner_dest_type) {
-                    quote! {
-                        dest.#dest_ident = self.#src_ident;
-                    }
-                // Handling the (Option<Option<<T>>, Option<T>) case
-                } else if is_equal_type(&inner_src_type, &outer_dest_type) {
-                    quote! {
-                        if let Some(value) = self.#src_ident {
-                            dest.#dest_ident = value;
-                        }
-                    }
-                // Handling the (Option<<T>, Option<Option<T>)> case
-                } else {
-                    equal_type_or_continue!(
-                        outer_src_type,
-                        inner_dest_type,
-                        "",
-                        quote! {
-                            dest.#dest_ident = Some(self.#src_ident);
-                        }
-                    )
-                }
-            }
-            // Skip anything where either of the fields are invalid
-            (FieldType::Invalid, _) | (_, FieldType::Invalid) => continue,
+    for pair in pairs {
+        let src_member = &pair.src_member;
+        let target_member = &pair.target_member;
+
+        // The src is consumed, so its values can simply be moved.
+        let field = quote!(self.#src_member);
+        let src = SrcAccess {
+            value: field.clone(),
+            option: field.clone(),
+            inner: quote!(value),
+            reference: None,
         };
+        let src = apply_ref_access(pair, src, &field);
+        let target = quote!(target.#target_member);
 
-        merge_code.extend(vec![snippet]);
+        if let Some(snippet) = merge_field(pair, &src, &target, flags) {
+            merge_code.extend(vec![snippet]);
+        }
     }
 
-    let merge_code = merge_code.to_token_stream();
+    merge_code
+}
+
+/// Generate the [struct_merge::StructMerge::merge] function for the given structs.
+fn merge(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
 
     let target_path = &params.target_path;
     quote! {
-        fn merge_into(self, dest: &mut #target_path) {
+        fn merge_into(self, target: &mut #target_path) {
             #merge_code
         }
     }
 }
 
 /// Generate the [struct_merge::StructMerge::merge_soft] function for the given structs.
-fn merge_soft(params: &Parameters, fields: Vec<(Field, Field)>) -> TokenStream {
-    let mut merge_code = TokenStream::new();
-    for (src_field, dest_field) in fields {
-        let src_ident = src_field.ident;
-        let dest_ident = dest_field.ident;
-
-        // Find out, whether the fields are optional or not.
-        let src_field_type = determine_field_type(src_field.ty);
-        let dest_field_type = determine_field_type(dest_field.ty);
-
-        let snippet = match (src_field_type, dest_field_type) {
-            // Soft merge only applies if the dest field is `Optional`.
-            (FieldType::Normal(_), FieldType::Normal(_))
-            | (FieldType::Optional { .. }, FieldType::Normal(_)) => continue,
-            // The dest is optional and needs to be wrapped in `Some(T)` to be merged.
-            (
-                FieldType::Normal(src_type),
-                FieldType::Optional {
-                    inner: dest_type, ..
-                },
-            ) => {
-                equal_type_or_continue!(
-                    src_type,
-                    dest_type,
-                    "",
-                    quote! {
-                        if dest.#dest_ident.is_none() {
-                            dest.#dest_ident = Some(self.#src_ident);
-                        }
-                    }
-                )
-            }
-            // Both fields are optional. It can now be either of these:
-            // - (Option<T>, Option<T>)
-            // - (Option<Option<T>>, Option<T>)
-            // - (Option<T>, Option<Option<T>>)
-            (
-                FieldType::Optional {
-                    inner: inner_src_type,
-                    outer: outer_src_type,
-                },
-                FieldType::Optional {
-                    inner: inner_dest_type,
-                    outer: outer_dest_type,
-                },
-            ) => {
-                // Handling the (Option<T>, Option<T>) case
-                if is_equal_type(&inner_src_type, &inner_dest_type) {
-                    quote! {
-                        if dest.#dest_ident.is_none() {
-                            dest.#dest_ident = self.#src_ident;
-                        }
-                    }
-                // Handling the (Option<Option<<T>>, Option<T>) case
-                } else if is_equal_type(&inner_src_type, &outer_dest_type) {
-                    quote! {
-                        if let Some(value) = self.#src_ident {
-                            if dest.#dest_ident.is_none() {
-                                dest.#dest_ident = value;
-                            }
-                        }
-                    }
-                // Handling the (Option<<T>, Option<Option<T>)> case
-                } else {
-                    equal_type_or_continue!(
-                        outer_src_type,
-                        inner_dest_type,
-                        "",
-                        quote! {
-                            if dest.#dest_ident.is_none() {
-                                dest.#dest_ident = Some(self.#src_ident);
-                            }
-                        }
-                    )
-                }
-            }
-            // Skip anything where either of the fields are invalid
-            (FieldType::Invalid, _) | (_, FieldType::Invalid) => continue,
-        };
+fn merge_soft(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
 
-        merge_code.extend(vec![snippet]);
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_soft(self, target: &mut #target_path) {
+            #merge_code
+        }
     }
+}
+
+/// Generate the [struct_merge::StructMerge::merge_report] function for the given structs.
+fn merge_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_report(self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
+            #merge_code
+            report
+        }
+    }
+}
 
-    let merge_code = merge_code.to_token_stream();
+/// Generate the [struct_merge::StructMerge::merge_soft_report] function for the given structs.
+fn merge_soft_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
 
     let target_path = &params.target_path;
     quote! {
-        fn merge_into_soft(self, dest: &mut #target_path) {
+        fn merge_into_soft_report(self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
             #merge_code
+            report
         }
     }
 }
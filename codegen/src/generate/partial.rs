@@ -0,0 +1,127 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Item, ItemStruct, Path};
+
+use super::*;
+use crate::attributes::{strip_helper_attributes, StructOptions};
+use crate::generics::resolve_generics;
+use crate::path::{PartialArguments, TargetOptions};
+
+/// Generate the partial struct of a target struct, whose fields are all optional, as well as
+/// its [struct_merge::StructMergeInto] and [struct_merge::StructMergeIntoRef] implementations.
+///
+/// Each field `T` becomes an `Option<T>`. Optional fields `Option<T>` become tri-state
+/// `struct_merge::Patch<T>`s, as serde can't tell a `null` from a missing `Option<Option<T>>`.
+/// Fields annotated with `#[merge(partial = "Type")]` become an `Option` or `Patch` of their
+/// partial type, which is merged via `#[merge(nested)]`.
+///
+/// The `#[merge(...)]` options of the target, such as `#[merge(diff)]`, apply to the partial
+/// struct.
+/// The partial struct is located in the target's file, so both share the same `imports`.
+pub(crate) fn generate_partial(
+    args: &PartialArguments,
+    target: &ItemStruct,
+    options: StructOptions,
+    imports: Imports,
+) -> Result<TokenStream, TokenStream> {
+    let mut partial = target.clone();
+    partial.ident = args.name.clone();
+
+    // Only doc comments are taken over from the target, everything else is set explicitly.
+    let target_ident = &target.ident;
+    let doc = format!(
+        " A partial [{}], whose fields are all optional.",
+        target_ident
+    );
+    partial.attrs = vec![syn::parse_quote!(#[doc = #doc])];
+    let derives = &args.derives;
+    if !derives.is_empty() {
+        partial
+            .attrs
+            .push(syn::parse_quote!(#[derive(#(#derives),*)]));
+    }
+    for attr in args.attrs.iter() {
+        partial.attrs.push(syn::parse_quote!(#[#attr]));
+    }
+
+    for field in partial.fields.iter_mut() {
+        // The field options have already been checked for errors.
+        let field_options = parse_field_options(&field.attrs).unwrap_or_default();
+        let field_type = override_field_type(
+            determine_field_type(field.ty.clone(), &options.option_aliases),
+            field_options.optional,
+        );
+
+        field.attrs.retain(is_doc_comment);
+        let ty = &field.ty;
+        field.ty = match (&field_options.partial, field_type) {
+            // The crate is referred to absolutely, as the `struct_merge` macro may be imported.
+            (Some(partial), FieldType::Optional { .. }) => {
+                field.attrs.push(syn::parse_quote!(#[merge(nested)]));
+                syn::parse_quote!(::struct_merge::Patch<#partial>)
+            }
+            (Some(partial), _) => {
+                field.attrs.push(syn::parse_quote!(#[merge(nested)]));
+                syn::parse_quote!(Option<#partial>)
+            }
+            // The inner type of fields, which are only optional via `#[merge(optional)]`, is
+            // unknown, so they're wrapped in another `Option` instead.
+            (None, FieldType::Optional { inner, .. }) if !matches!(inner, Type::Infer(_)) => {
+                syn::parse_quote!(::struct_merge::Patch<#inner>)
+            }
+            (None, _) => syn::parse_quote!(Option<#ty>),
+        };
+    }
+
+    // The partial struct has the same generic parameters as the target.
+    let mut target_path = Path::from(target_ident.clone());
+    let mut target_struct = target.clone();
+    let generics = resolve_generics(&partial.generics, &mut target_path, &mut target_struct)?;
+
+    // The values of the borrowed partial struct are cloned.
+    // Diffs additionally compare the values of the target.
+    let mut borrowed_generics = generics.clone();
+    let type_params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = borrowed_generics.make_where_clause();
+    for param in type_params {
+        let predicate = if options.diff {
+            syn::parse_quote!(#param: Clone + PartialEq)
+        } else {
+            syn::parse_quote!(#param: Clone)
+        };
+        where_clause.predicates.push(predicate);
+    }
+
+    let mut tokens = TokenStream::new();
+    for (mode, generics) in [(Mode::Owned, generics), (Mode::Borrowed, borrowed_generics)] {
+        let params = Parameters {
+            src_struct: partial.clone(),
+            src_path: None,
+            target_path: target_path.clone(),
+            target_struct: target_struct.clone(),
+            generics,
+            options: options.clone(),
+            target_options: TargetOptions::default(),
+            src_imports: imports.clone(),
+            target_imports: imports.clone(),
+        };
+        tokens.extend(vec![generate_impl(&mode, params)?]);
+    }
+
+    // The compiler doesn't know our `#[merge(nested)]` attributes.
+    let mut partial = Item::Struct(partial);
+    strip_helper_attributes(&mut partial);
+
+    Ok(quote! {
+        #partial
+        #tokens
+    })
+}
+
+/// Check whether the attribute is a doc comment, i.e. `/// ...` or `#[doc = "..."]`.
+fn is_doc_comment(attr: &Attribute) -> bool {
+    attr.path.is_ident("doc")
+}
@@ -0,0 +1,145 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{GenericArgument, PathArguments, Type};
+
+use super::{is_equal_type, TypeContext};
+
+/// Smart pointers, which are transparently wrapped and unwrapped during merges.
+#[derive(Clone, Copy)]
+pub(super) enum Pointer {
+    Box,
+    Rc,
+    Arc,
+    Cow,
+}
+
+impl Pointer {
+    /// Wrap a value into this pointer, e.g. `Box::new(value)`.
+    fn wrap(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            Pointer::Box => quote!(std::boxed::Box::new(#value)),
+            Pointer::Rc => quote!(std::rc::Rc::new(#value)),
+            Pointer::Arc => quote!(std::sync::Arc::new(#value)),
+            Pointer::Cow => quote!(std::borrow::Cow::Owned(#value)),
+        }
+    }
+
+    /// Take the value out of this pointer.
+    /// The values of shared pointers are cloned.
+    fn unwrap(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            Pointer::Box => quote!(*#value),
+            Pointer::Rc | Pointer::Arc => quote!(Clone::clone(&*#value)),
+            Pointer::Cow => quote!(#value.into_owned()),
+        }
+    }
+
+    /// Get a mutable reference to the value behind the `target` pointer expression.
+    /// Shared values are cloned, if there are other references to them.
+    pub(super) fn as_mut(&self, target: &TokenStream) -> TokenStream {
+        match self {
+            Pointer::Box => quote!(&mut *#target),
+            Pointer::Rc => quote!(std::rc::Rc::make_mut(&mut #target)),
+            Pointer::Arc => quote!(std::sync::Arc::make_mut(&mut #target)),
+            Pointer::Cow => quote!(#target.to_mut()),
+        }
+    }
+}
+
+/// Describes how a src value is converted into the type of its target field.
+#[derive(Clone, Copy)]
+pub(super) enum PointerConversion {
+    /// The target is a pointer to the src's type, e.g. `T` into `Arc<T>`.
+    Wrap(Pointer),
+    /// The src is a pointer to the target's type, e.g. `Box<T>` into `T`.
+    Unwrap(Pointer),
+}
+
+impl PointerConversion {
+    /// Convert the given src value.
+    pub(super) fn apply(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            PointerConversion::Wrap(pointer) => pointer.wrap(value),
+            PointerConversion::Unwrap(pointer) => pointer.unwrap(value),
+        }
+    }
+
+    /// Convert the given reference to a target value back into the src's type.
+    /// The value is cloned.
+    pub(super) fn revert(&self, reference: &TokenStream) -> TokenStream {
+        match self {
+            PointerConversion::Wrap(_) => quote!(std::borrow::ToOwned::to_owned(&**#reference)),
+            PointerConversion::Unwrap(pointer) => pointer.wrap(&quote!(Clone::clone(#reference))),
+        }
+    }
+}
+
+/// Check whether a type is one of the supported smart pointers, such as `Arc<T>`.
+///
+/// Returns the pointer and the owned type of its value.
+/// For `Cow<'a, str>` and `Cow<'a, [T]>`, that's `String` and `Vec<T>`.
+pub(super) fn determine_pointer(ty: &Type) -> Option<(Pointer, Type)> {
+    let path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+
+    let pointer = match segment.ident.to_string().as_str() {
+        "Box" => Pointer::Box,
+        "Rc" => Pointer::Rc,
+        "Arc" => Pointer::Arc,
+        "Cow" => Pointer::Cow,
+        _ => return None,
+    };
+
+    // The value is the first type argument, e.g. `str` for `Cow<'a, str>`.
+    let inner = match &segment.arguments {
+        PathArguments::AngleBracketed(params) => params.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(inner) => Some(inner.clone()),
+            _ => None,
+        })?,
+        _ => return None,
+    };
+
+    if !matches!(pointer, Pointer::Cow) {
+        return Some((pointer, inner));
+    }
+
+    Some((pointer, owned_type(inner)))
+}
+
+/// Get the owned type of a borrowed value via `ToOwned`, e.g. `String` for `str`, `Vec<T>` for
+/// `[T]` and `T` for any other `T: Clone`.
+pub(super) fn owned_type(ty: Type) -> Type {
+    match ty {
+        Type::Path(type_path) if type_path.path.is_ident("str") => syn::parse_quote!(String),
+        Type::Slice(slice) => {
+            let element = slice.elem;
+            syn::parse_quote!(Vec<#element>)
+        }
+        ty => ty,
+    }
+}
+
+/// Determine how a src value of type `src_type` can be converted into a `target_type`, if either
+/// of them is a smart pointer to the other.
+pub(super) fn determine_pointer_conversion(
+    src_type: &Type,
+    target_type: &Type,
+    context: TypeContext,
+) -> Option<PointerConversion> {
+    if let Some((pointer, inner)) = determine_pointer(target_type) {
+        if is_equal_type(src_type, &inner, context) {
+            return Some(PointerConversion::Wrap(pointer));
+        }
+    }
+
+    if let Some((pointer, inner)) = determine_pointer(src_type) {
+        if is_equal_type(&inner, target_type, context) {
+            return Some(PointerConversion::Unwrap(pointer));
+        }
+    }
+
+    None
+}
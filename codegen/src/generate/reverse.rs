@@ -0,0 +1,125 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Generics, Member, Path};
+
+use super::*;
+use crate::attributes::is_helper_attribute;
+
+/// Determine the parameters to merge the target struct back into the src struct, which is
+/// located at `src_path`.
+///
+/// The target struct becomes the src. Its field options are derived from the options of the
+/// src's fields, e.g. `#[merge(name = "b")] a` results in `#[merge(name = "a")] b` and
+/// `#[merge(optional)]` results in `#[merge(target_optional)]`.
+/// Options of the target's fields are ignored, as they belong to other merges of the target.
+/// Target fields without a respective src field are skipped.
+///
+/// The struct options apply to both directions, except for `extract` and `diff`, which would
+/// require the target to cover all fields of the src.
+pub(crate) fn reverse_parameters(
+    params: &Parameters,
+    src_path: Path,
+) -> Result<Parameters, TokenStream> {
+    let src_struct = &params.src_struct;
+    let target_struct = &params.target_struct;
+    if !src_struct.generics.params.is_empty() || !target_struct.generics.params.is_empty() {
+        return Err(err!(
+            src_struct.ident,
+            "Bidirectional merges aren't supported for generic structs yet."
+        ));
+    }
+
+    let mut reversed_src = target_struct.clone();
+    for field in reversed_src.fields.iter_mut() {
+        field.attrs.retain(|attr| !is_helper_attribute(attr));
+    }
+    let target_members: Vec<Member> = members(&target_struct.fields)
+        .into_iter()
+        .map(|(member, _)| member)
+        .collect();
+
+    // Errors of missing target fields have already been reported by the forward merge.
+    let mut errors = TokenStream::new();
+    let fields = match_fields(
+        &src_struct.fields,
+        &target_struct.fields,
+        &target_struct.ident,
+        &params.target_options,
+        &mut TokenStream::new(),
+    );
+
+    // Target fields, that aren't merged into, aren't merged back either.
+    let skip = target_members
+        .iter()
+        .filter(|member| !fields.iter().any(|field| field.target_member == **member))
+        .cloned()
+        .collect();
+
+    for field in fields {
+        // The field options have already been checked for errors.
+        let options = parse_field_options(&field.src.attrs).unwrap_or_default();
+        if options.nested {
+            errors.extend(vec![err!(
+                field.src,
+                "Nested fields cannot be merged bidirectionally."
+            )]);
+            continue;
+        }
+
+        let mut reversed_options = Vec::new();
+        if field.src_member != field.target_member {
+            reversed_options.push(match &field.src_member {
+                Member::Named(ident) => {
+                    let name = ident.to_string();
+                    quote!(name = #name)
+                }
+                Member::Unnamed(index) => quote!(index = #index),
+            });
+        }
+        match options.optional {
+            Some(true) => reversed_options.push(quote!(target_optional)),
+            Some(false) => reversed_options.push(quote!(target_required)),
+            None => {}
+        }
+        match options.target_optional {
+            Some(true) => reversed_options.push(quote!(optional)),
+            Some(false) => reversed_options.push(quote!(required)),
+            None => {}
+        }
+
+        let position = target_members
+            .iter()
+            .position(|member| *member == field.target_member);
+        if let (Some(position), false) = (position, reversed_options.is_empty()) {
+            if let Some(target_field) = reversed_src.fields.iter_mut().nth(position) {
+                target_field
+                    .attrs
+                    .push(syn::parse_quote!(#[merge(#(#reversed_options),*)]));
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut options = params.options.clone();
+    options.extract = false;
+    options.diff = false;
+
+    // The reversed impls are located in the src's file, so the target is referred to via its path.
+    Ok(Parameters {
+        src_struct: reversed_src,
+        src_path: Some(params.target_path.clone()),
+        target_path: src_path,
+        target_struct: src_struct.clone(),
+        generics: Generics::default(),
+        options,
+        target_options: TargetOptions {
+            skip,
+            ..TargetOptions::default()
+        },
+        src_imports: params.target_imports.clone(),
+        target_imports: params.src_imports.clone(),
+    })
+}
@@ -0,0 +1,159 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::*;
+
+/// Generate the implementation of [struct_merge::StructMergeTake] for given structs.
+pub(crate) fn impl_take(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    let mut errors = TokenStream::new();
+    let mut pairs = determine_field_pairs(fields, params.type_context(), &mut errors);
+
+    // Such fields would be taken as a whole, which drops any inner values that a soft merge
+    // doesn't write.
+    pairs.retain(|pair| {
+        if pair.conversion.nested {
+            errors.extend(vec![err!(
+                pair.src_member,
+                "Nested fields aren't supported for take merges yet."
+            )]);
+            return false;
+        }
+        if pair.conversion.elements {
+            errors.extend(vec![err!(
+                pair.src_member,
+                "Arrays with optional elements aren't supported for take merges yet."
+            )]);
+            return false;
+        }
+        true
+    });
+
+    let mut functions_tokens = TokenStream::new();
+
+    let stream = merge_take(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_take_soft(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_take_report(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let stream = merge_take_soft_report(params, &pairs);
+    functions_tokens.extend(vec![stream]);
+
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
+    let target_path = &params.target_path;
+    quote! {
+        #errors
+
+        impl #impl_generics struct_merge::StructMergeIntoTake<#target_path> for #src_type #where_clause {
+            #functions_tokens
+        }
+    }
+}
+
+/// Generate the code that merges all fields of `&mut self` into `target`.
+///
+/// All fields, which aren't optional, must implement `Default`.
+/// Arrays are taken as a whole. Nested fields and arrays with optional elements are rejected by
+/// [impl_take].
+fn merge_code(pairs: &[FieldPair], flags: MergeFlags) -> TokenStream {
+    let mut merge_code = TokenStream::new();
+    for pair in pairs {
+        let src_member = &pair.src_member;
+        let target_member = &pair.target_member;
+
+        // The values are moved out of the src, which is left with their defaults.
+        let field = quote!(self.#src_member);
+        let src = SrcAccess {
+            value: quote!(std::mem::take(&mut #field)),
+            option: quote!(#field.take()),
+            inner: quote!(value),
+            reference: None,
+        };
+        let src = apply_ref_access(pair, src, &field);
+        let target = quote!(target.#target_member);
+
+        if let Some(snippet) = merge_field(pair, &src, &target, flags) {
+            merge_code.extend(vec![snippet]);
+        }
+    }
+
+    merge_code
+}
+
+/// Generate the [struct_merge::StructMergeTake::merge_take] function for the given structs.
+fn merge_take(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_take(&mut self, target: &mut #target_path) {
+            #merge_code
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergeTake::merge_take_soft] function for the given structs.
+fn merge_take_soft(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: false,
+        track_changes: false,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_take_soft(&mut self, target: &mut #target_path) {
+            #merge_code
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergeTake::merge_take_report] function for the given structs.
+fn merge_take_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: false,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_take_report(&mut self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
+            #merge_code
+            report
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMergeTake::merge_take_soft_report] function for the given structs.
+fn merge_take_soft_report(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let flags = MergeFlags {
+        soft: true,
+        report: true,
+        track_changes: params.options.track_changes,
+    };
+    let merge_code = merge_code(pairs, flags);
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge_into_take_soft_report(&mut self, target: &mut #target_path) -> struct_merge::MergeReport {
+            #[allow(unused_mut)]
+            let mut report = struct_merge::MergeReport::default();
+            #merge_code
+            report
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::*;
+
+/// Generate the implementation of [struct_merge::StructMerge3Into] for given structs.
+pub(crate) fn impl_three_way(params: &Parameters, fields: Vec<FieldMatch>) -> TokenStream {
+    let mut errors = TokenStream::new();
+    let pairs = determine_field_pairs(fields, params.type_context(), &mut errors);
+    let pairs = reject_conversions(pairs, "three-way merges", &mut errors);
+
+    let stream = merge3(params, &pairs);
+
+    let src_type = params.src_type();
+    let (impl_generics, _, where_clause) = params.generics.split_for_impl();
+    let target_path = &params.target_path;
+    quote! {
+        #errors
+
+        impl #impl_generics struct_merge::StructMerge3Into<#target_path> for #src_type #where_clause {
+            #stream
+        }
+    }
+}
+
+/// Generate the [struct_merge::StructMerge3::merge3] function for given structs.
+///
+/// All fields must implement `Clone` and `PartialEq`.
+///
+/// `self` is the changed version of the src struct (theirs), while `base` is the common ancestor.
+/// A field is only merged, if `theirs` changed it while the target (ours) didn't.
+/// If both sides changed a field to different values, the field is reported as a conflict.
+fn merge3(params: &Parameters, pairs: &[FieldPair]) -> TokenStream {
+    let mut merge_code = TokenStream::new();
+    for pair in pairs {
+        let src_member = &pair.src_member;
+        let target_member = &pair.target_member;
+        let field_name = &pair.name;
+
+        if pair.conversion.pointer.is_some() || pair.conversion.nested {
+            merge_code.extend(vec![err!(
+                src_member,
+                "Smart pointers and nested fields aren't supported for three-way merges yet."
+            )]);
+            continue;
+        }
+        if pair.conversion.patch {
+            merge_code.extend(vec![err!(
+                src_member,
+                "Patch fields aren't supported for three-way merges yet."
+            )]);
+            continue;
+        }
+
+        merge_code.extend(vec![pair.type_checks.clone()]);
+
+        // The elements of arrays are merged one by one.
+        // The field is reported once, if any of its elements conflict.
+        if pair.conversion.elements {
+            let snippet = merge3_value(
+                pair.conversion.conversion,
+                &quote!(self.#src_member[index]),
+                &quote!(base.#src_member[index]),
+                &quote!(target.#target_member[index]),
+                &quote!(conflict = true;),
+            );
+            merge_code.extend(vec![quote! {
+                {
+                    let mut conflict = false;
+                    for index in 0..target.#target_member.len() {
+                        #snippet
+                    }
+                    if conflict {
+                        conflicts.push(#field_name);
+                    }
+                }
+            }]);
+            continue;
+        }
+
+        let snippet = merge3_value(
+            pair.conversion.conversion,
+            &quote!(self.#src_member),
+            &quote!(base.#src_member),
+            &quote!(target.#target_member),
+            &quote!(conflicts.push(#field_name);),
+        );
+        merge_code.extend(vec![snippet]);
+    }
+
+    let target_path = &params.target_path;
+    quote! {
+        fn merge3_into(&self, base: &Self, target: &mut #target_path) -> Vec<&'static str> {
+            #[allow(unused_mut)]
+            let mut conflicts = Vec::new();
+            #merge_code
+            conflicts
+        }
+    }
+}
+
+/// Generate the code that merges a single value of `theirs` into `target`, given the value in
+/// `base`. The `conflict` code is executed, if both sides changed the value.
+fn merge3_value(
+    conversion: Conversion,
+    theirs: &TokenStream,
+    base: &TokenStream,
+    target: &TokenStream,
+    conflict: &TokenStream,
+) -> TokenStream {
+    match conversion {
+        // Both fields have the same type.
+        Conversion::Direct => quote! {
+            if #theirs != #base {
+                if #target == #base {
+                    #target = Clone::clone(&#theirs);
+                } else if #target != #theirs {
+                    #conflict
+                }
+            }
+        },
+        // The target is optional and the value needs to be wrapped in `Some(T)`.
+        Conversion::Wrap => quote! {
+            if #theirs != #base {
+                if #target.as_ref() == Some(&#base) {
+                    #target = Some(Clone::clone(&#theirs));
+                } else if #target.as_ref() != Some(&#theirs) {
+                    #conflict
+                }
+            }
+        },
+        // The src is optional and only `Some(T)` values are merged.
+        // If the base didn't have a value, we cannot know whether the target has been
+        // changed. Hence, differing values are considered a conflict.
+        Conversion::Unwrap => quote! {
+            if #theirs != #base {
+                if let Some(value) = #theirs.as_ref() {
+                    if #base.as_ref() == Some(&#target) {
+                        #target = Clone::clone(value);
+                    } else if #target != *value {
+                        #conflict
+                    }
+                }
+            }
+        },
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    AngleBracketedGenericArguments, Expr, Field, GenericArgument, GenericParam, Generics, Ident,
+    ItemEnum, ItemStruct, Lifetime, Path, PathArguments, Type, WhereClause,
+};
+
+/// Items that can be the target of a merge.
+pub trait TargetItem {
+    fn ident(&self) -> &Ident;
+    fn generics(&self) -> &Generics;
+    /// All fields of the item, including the fields of all enum variants.
+    fn fields_mut(&mut self) -> Vec<&mut Field>;
+}
+
+impl TargetItem for ItemStruct {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    fn fields_mut(&mut self) -> Vec<&mut Field> {
+        self.fields.iter_mut().collect()
+    }
+}
+
+impl TargetItem for ItemEnum {
+    fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    fn fields_mut(&mut self) -> Vec<&mut Field> {
+        self.variants
+            .iter_mut()
+            .flat_map(|variant| variant.fields.iter_mut())
+            .collect()
+    }
+}
+
+/// Generic parameters of the target struct, which are bound to concrete arguments via the macro
+/// attribute. E.g. `T => String` for `#[struct_merge(crate::Target<String>)]`.
+#[derive(Default)]
+struct Substitutions {
+    types: HashMap<Ident, Type>,
+    consts: HashMap<Ident, Expr>,
+    lifetimes: HashMap<Ident, Lifetime>,
+}
+
+impl VisitMut for Substitutions {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Some(ident) = single_ident(ty) {
+            if let Some(replacement) = self.types.get(ident) {
+                *ty = replacement.clone();
+                return;
+            }
+        }
+
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Path(expr_path) = expr {
+            if let Some(ident) = expr_path.path.get_ident() {
+                if let Some(replacement) = self.consts.get(ident) {
+                    *expr = replacement.clone();
+                    return;
+                }
+            }
+        }
+
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if let Some(replacement) = self.lifetimes.get(&lifetime.ident) {
+            *lifetime = replacement.clone();
+        }
+    }
+}
+
+/// Return the identifier of a type that consists of a single identifier, such as `T`.
+fn single_ident(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path.path.get_ident(),
+        _ => None,
+    }
+}
+
+/// Resolve the generics of the impl that merges an item with `src_generics` into `target`.
+///
+/// Generic parameters of the target can be bound to concrete arguments via the target path,
+/// e.g. `crate::Target<String>`. These parameters are then substituted in the fields of the
+/// `target`.
+/// All unbound parameters of the target become parameters of the impl.
+/// Parameters of both structs with the same name are considered to be the same parameter.
+///
+/// The `target_path` is updated to contain all generic arguments of the target, so it can be
+/// used as a type in the generated code.
+///
+/// Returns the generics of the generated impl.
+pub fn resolve_generics(
+    src_generics: &Generics,
+    target_path: &mut Path,
+    target: &mut impl TargetItem,
+) -> Result<Generics, TokenStream> {
+    let original_path = target_path.clone();
+    let segment = match target_path.segments.last_mut() {
+        Some(segment) => segment,
+        None => return Err(err!(original_path, "Expected a path to the target struct.")),
+    };
+
+    // Split the given arguments into lifetimes and all other arguments.
+    let mut lifetime_args = Vec::new();
+    let mut other_args = Vec::new();
+    match std::mem::replace(&mut segment.arguments, PathArguments::None) {
+        PathArguments::None => {}
+        PathArguments::AngleBracketed(arguments) => {
+            for arg in arguments.args {
+                match arg {
+                    GenericArgument::Lifetime(lifetime) => lifetime_args.push(lifetime),
+                    GenericArgument::Type(_) | GenericArgument::Const(_) => other_args.push(arg),
+                    _ => return Err(err!(
+                        arg,
+                        "Only lifetimes, types and constants are supported as generic arguments."
+                    )),
+                }
+            }
+        }
+        PathArguments::Parenthesized(arguments) => {
+            return Err(err!(
+                arguments,
+                "Parenthesized arguments aren't supported for target structs."
+            ))
+        }
+    }
+
+    let target_params = target.generics().params.clone();
+    let lifetime_count = target.generics().lifetimes().count();
+    let other_count = target_params.len() - lifetime_count;
+    if !lifetime_args.is_empty() && lifetime_args.len() != lifetime_count {
+        return Err(err!(
+            original_path,
+            "Expected {} lifetime arguments for struct {}.",
+            lifetime_count,
+            target.ident()
+        ));
+    }
+    if !other_args.is_empty() && other_args.len() != other_count {
+        return Err(err!(
+            original_path,
+            "Expected {} generic arguments for struct {}.",
+            other_count,
+            target.ident()
+        ));
+    }
+
+    let mut lifetime_args = lifetime_args.into_iter();
+    let mut other_args = other_args.into_iter();
+    let mut substitutions = Substitutions::default();
+    // All arguments of the target type in the generated code.
+    let mut target_args: Punctuated<GenericArgument, syn::Token![,]> = Punctuated::new();
+    // All parameters of the target that aren't bound to an argument.
+    let mut free_params = Vec::new();
+
+    for param in target_params {
+        match param {
+            GenericParam::Lifetime(def) => match lifetime_args.next() {
+                Some(lifetime) => {
+                    substitutions
+                        .lifetimes
+                        .insert(def.lifetime.ident.clone(), lifetime.clone());
+                    target_args.push(GenericArgument::Lifetime(lifetime));
+                }
+                None => {
+                    target_args.push(GenericArgument::Lifetime(def.lifetime.clone()));
+                    free_params.push(GenericParam::Lifetime(def));
+                }
+            },
+            GenericParam::Type(type_param) => match other_args.next() {
+                Some(GenericArgument::Type(ty)) => {
+                    substitutions
+                        .types
+                        .insert(type_param.ident.clone(), ty.clone());
+                    target_args.push(GenericArgument::Type(ty));
+                }
+                Some(arg) => {
+                    return Err(err!(
+                        arg,
+                        "Expected a type for generic parameter {}.",
+                        type_param.ident
+                    ))
+                }
+                None => {
+                    let ident = &type_param.ident;
+                    target_args.push(GenericArgument::Type(syn::parse_quote!(#ident)));
+                    free_params.push(GenericParam::Type(type_param));
+                }
+            },
+            GenericParam::Const(const_param) => match other_args.next() {
+                // Constants that consist of a single identifier are parsed as types.
+                Some(GenericArgument::Type(ty)) => {
+                    let expr: Expr = syn::parse_quote!(#ty);
+                    substitutions
+                        .consts
+                        .insert(const_param.ident.clone(), expr.clone());
+                    target_args.push(GenericArgument::Const(expr));
+                }
+                Some(GenericArgument::Const(expr)) => {
+                    substitutions
+                        .consts
+                        .insert(const_param.ident.clone(), expr.clone());
+                    target_args.push(GenericArgument::Const(expr));
+                }
+                Some(arg) => {
+                    return Err(err!(
+                        arg,
+                        "Expected a constant for generic parameter {}.",
+                        const_param.ident
+                    ))
+                }
+                None => {
+                    let ident = &const_param.ident;
+                    target_args.push(GenericArgument::Const(syn::parse_quote!(#ident)));
+                    free_params.push(GenericParam::Const(const_param));
+                }
+            },
+        }
+    }
+
+    // Put the full list of arguments back into the target path.
+    if !target_args.is_empty() {
+        segment.arguments = PathArguments::AngleBracketed(AngleBracketedGenericArguments {
+            colon2_token: None,
+            lt_token: Default::default(),
+            args: target_args,
+            gt_token: Default::default(),
+        });
+    }
+
+    // Replace all bound parameters in the target.
+    for field in target.fields_mut() {
+        substitutions.visit_type_mut(&mut field.ty);
+    }
+    let mut target_where_clause = target.generics().where_clause.clone();
+    if let Some(where_clause) = target_where_clause.as_mut() {
+        substitutions.visit_where_clause_mut(where_clause);
+    }
+
+    // The impl has all parameters of the src and all free parameters of the target.
+    let mut generics = src_generics.clone();
+    let mut predicates = Vec::new();
+    for param in free_params {
+        let existing = generics
+            .params
+            .iter()
+            .find(|existing| match (existing, &param) {
+                (GenericParam::Lifetime(existing), GenericParam::Lifetime(param)) => {
+                    existing.lifetime == param.lifetime
+                }
+                (GenericParam::Type(existing), GenericParam::Type(param)) => {
+                    existing.ident == param.ident
+                }
+                (GenericParam::Const(existing), GenericParam::Const(param)) => {
+                    existing.ident == param.ident
+                }
+                _ => false,
+            });
+
+        if existing.is_none() {
+            generics.params.push(param);
+            continue;
+        }
+
+        // The parameter already exists on the src.
+        // Its bounds on the target still need to be satisfied.
+        match param {
+            GenericParam::Lifetime(def) if !def.bounds.is_empty() => {
+                let lifetime = def.lifetime;
+                let bounds = def.bounds;
+                predicates.push(syn::parse_quote!(#lifetime: #bounds));
+            }
+            GenericParam::Type(type_param) if !type_param.bounds.is_empty() => {
+                let ident = type_param.ident;
+                let bounds = type_param.bounds;
+                predicates.push(syn::parse_quote!(#ident: #bounds));
+            }
+            _ => {}
+        }
+    }
+    if let Some(where_clause) = target_where_clause {
+        predicates.extend(where_clause.predicates);
+    }
+    if !predicates.is_empty() {
+        let where_clause = generics.where_clause.get_or_insert_with(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Punctuated::new(),
+        });
+        where_clause.predicates.extend(predicates);
+    }
+
+    // Lifetimes have to be declared before all other parameters.
+    // Defaults aren't allowed on impls.
+    let (lifetimes, others): (Vec<_>, Vec<_>) = generics
+        .params
+        .into_iter()
+        .partition(|param| matches!(param, GenericParam::Lifetime(_)));
+    generics.params = lifetimes
+        .into_iter()
+        .chain(others)
+        .map(|mut param| {
+            match &mut param {
+                GenericParam::Type(type_param) => {
+                    type_param.eq_token = None;
+                    type_param.default = None;
+                }
+                GenericParam::Const(const_param) => {
+                    const_param.eq_token = None;
+                    const_param.default = None;
+                }
+                GenericParam::Lifetime(_) => {}
+            }
+            param
+        })
+        .collect();
+
+    Ok(generics)
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::path::Path as FilePath;
+
+use syn::visit_mut::{self, VisitMut};
+use syn::{Ident, Item, Path, PathArguments, PathSegment, Type, TypePath, UseTree};
+
+/// Types of the prelude and their canonical paths.
+const PRELUDE: &[(&str, &str)] = &[
+    ("Option", "std::option::Option"),
+    ("Result", "std::result::Result"),
+    ("Vec", "std::vec::Vec"),
+    ("String", "std::string::String"),
+    ("Box", "std::boxed::Box"),
+];
+
+/// The names, that are known in a single file, and their canonical paths.
+///
+/// These are the names that are imported via `use` items and the types that are declared in
+/// that file. This is used to compare types, that are written differently in the src and the
+/// target file, e.g. `Vec<T>` and `std::vec::Vec<T>`.
+#[derive(Clone, Default)]
+pub struct Imports {
+    names: HashMap<Ident, Path>,
+    /// The path of the file's module, e.g. `crate::structs`, against which `self::` and
+    /// `super::` paths are resolved.
+    module: Option<Path>,
+}
+
+impl Imports {
+    /// Collect the imports of the given items of a file.
+    ///
+    /// `module` is the path of the module of that file, e.g. `crate::structs`.
+    pub fn new(items: &[Item], module: Option<&Path>) -> Imports {
+        let mut imports = Imports {
+            module: module.cloned(),
+            ..Imports::default()
+        };
+        for item in items {
+            let ident = match item {
+                Item::Use(item_use) => {
+                    imports.add_use_tree(&item_use.tree, &mut Vec::new());
+                    continue;
+                }
+                Item::Struct(item) => &item.ident,
+                Item::Enum(item) => &item.ident,
+                Item::Union(item) => &item.ident,
+                Item::Type(item) => &item.ident,
+                Item::Mod(item) => &item.ident,
+                _ => continue,
+            };
+
+            if let Some(module) = module {
+                let mut path = module.clone();
+                path.segments.push(PathSegment::from(ident.clone()));
+                imports.names.insert(ident.clone(), path);
+            }
+        }
+
+        imports
+    }
+
+    /// Read and parse the given file and collect its imports.
+    ///
+    /// Files that cannot be read simply don't have any imports, as types are also checked by the
+    /// compiler later on.
+    pub fn from_file(file_path: &FilePath, module: Option<&Path>) -> Imports {
+        let file = std::fs::read_to_string(file_path)
+            .ok()
+            .and_then(|content| syn::parse_file(&content).ok());
+
+        match file {
+            Some(file) => Imports::new(&file.items, module),
+            None => Imports::default(),
+        }
+    }
+
+    /// Add all names of a `use` tree, e.g. `use std::sync::{Arc, Mutex as Lock};`.
+    /// Glob imports are ignored.
+    fn add_use_tree(&mut self, tree: &UseTree, prefix: &mut Vec<Ident>) {
+        match tree {
+            UseTree::Path(use_path) => {
+                prefix.push(use_path.ident.clone());
+                self.add_use_tree(&use_path.tree, prefix);
+                prefix.pop();
+            }
+            UseTree::Name(use_name) => self.add_name(&use_name.ident, &use_name.ident, prefix),
+            UseTree::Rename(use_rename) => {
+                self.add_name(&use_rename.rename, &use_rename.ident, prefix)
+            }
+            UseTree::Group(group) => {
+                for tree in group.items.iter() {
+                    self.add_use_tree(tree, prefix);
+                }
+            }
+            UseTree::Glob(_) => {}
+        }
+    }
+
+    /// Add the imported `ident` under the given `name`.
+    /// `self` imports the module of the prefix itself.
+    fn add_name(&mut self, name: &Ident, ident: &Ident, prefix: &[Ident]) {
+        let mut segments = prefix.to_vec();
+        if ident != "self" {
+            segments.push(ident.clone());
+        }
+        let name = match (name == "self", segments.last()) {
+            (true, Some(last)) => last.clone(),
+            (true, None) => return,
+            (false, _) => name.clone(),
+        };
+
+        let mut path = Path {
+            leading_colon: None,
+            segments: Default::default(),
+        };
+        for segment in segments {
+            path.segments.push(PathSegment::from(segment));
+        }
+        self.names.insert(name, path);
+    }
+
+    /// Resolve all paths in the given type to their canonical paths.
+    ///
+    /// Imported and declared names are replaced by their full path, types of the prelude by
+    /// their path in `std`. `core::` and `alloc::` paths are treated as `std::` paths.
+    /// `self::` and `super::` paths are resolved against the file's module.
+    /// Unknown names and relative paths of unknown modules are kept as they are.
+    pub fn normalize(&self, ty: &mut Type) {
+        Normalizer { imports: self }.visit_type_mut(ty);
+    }
+
+    /// Resolve the first segment of the given path.
+    fn resolve(&self, path: &mut Path) {
+        // Relative paths, such as `self::Type`, can only be resolved for imports.
+        // Paths with a leading `::` start at a crate, which can't be shadowed by imports.
+        let absolute = path.leading_colon.take().is_some();
+        let first = match path.segments.first() {
+            Some(first) => first.clone(),
+            None => return,
+        };
+
+        let resolved = match self.names.get(&first.ident) {
+            _ if absolute => None,
+            Some(resolved) => Some(resolved.clone()),
+            None if path.segments.len() == 1 => PRELUDE
+                .iter()
+                .find(|(name, _)| first.ident == name)
+                .map(|(_, canonical)| syn::parse_str::<Path>(canonical).unwrap()),
+            None => None,
+        };
+
+        if let Some(mut resolved) = resolved {
+            // The generic arguments of the first segment belong to the resolved item.
+            if let Some(last) = resolved.segments.last_mut() {
+                last.arguments = first.arguments;
+            }
+            resolved
+                .segments
+                .extend(path.segments.iter().skip(1).cloned());
+            *path = resolved;
+        }
+
+        if !absolute {
+            self.make_absolute(path);
+        }
+
+        if let Some(root) = path.segments.first_mut() {
+            if (root.ident == "core" || root.ident == "alloc")
+                && matches!(root.arguments, PathArguments::None)
+            {
+                root.ident = Ident::new("std", root.ident.span());
+            }
+        }
+    }
+
+    /// Replace leading `self` and `super` segments of a path with the path of the file's module.
+    ///
+    /// The path is kept as it is, if the module is unknown or there are too many `super`s.
+    fn make_absolute(&self, path: &mut Path) {
+        let first = match path.segments.first() {
+            Some(first) if first.ident == "self" || first.ident == "super" => first,
+            _ => return,
+        };
+        let mut module: Vec<PathSegment> = match &self.module {
+            Some(module) => module.segments.iter().cloned().collect(),
+            None => return,
+        };
+
+        let mut relative = path.segments.iter().peekable();
+        if first.ident == "self" {
+            relative.next();
+        }
+        while let Some(segment) = relative.peek() {
+            if segment.ident != "super" {
+                break;
+            }
+            // `crate` itself doesn't have a parent.
+            if module.len() < 2 {
+                return;
+            }
+            module.pop();
+            relative.next();
+        }
+
+        let mut segments = module;
+        segments.extend(relative.cloned());
+        path.segments = segments.into_iter().collect();
+    }
+}
+
+/// Resolves all paths of a type via [Imports::resolve].
+struct Normalizer<'a> {
+    imports: &'a Imports,
+}
+
+impl VisitMut for Normalizer<'_> {
+    fn visit_type_path_mut(&mut self, type_path: &mut TypePath) {
+        if type_path.qself.is_none() {
+            self.imports.resolve(&mut type_path.path);
+        }
+        visit_mut::visit_type_path_mut(self, type_path);
+    }
+}
+
+/// Get the path of the module of a source file, e.g. `crate::structs` for `src/structs.rs`.
+///
+/// `lib.rs`, `main.rs` and `mod.rs` are the files of their parent module.
+/// The compiler might provide file paths relative to its working directory.
+pub fn module_path_of_file(src_root_path: &FilePath, file_path: &FilePath) -> Option<Path> {
+    let src_root_path = src_root_path.canonicalize().ok()?;
+    let file_path = file_path.canonicalize().ok()?;
+    let relative = file_path
+        .strip_prefix(src_root_path)
+        .ok()?
+        .with_extension("");
+
+    let mut components: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let is_root_file = components.len() == 1
+        && matches!(components.last().map(String::as_str), Some("lib" | "main"));
+    if is_root_file || components.last().map(String::as_str) == Some("mod") {
+        components.pop();
+    }
+
+    let mut path = syn::parse_str::<Path>("crate").ok()?;
+    for component in components {
+        let ident = syn::parse_str::<Ident>(&component).ok()?;
+        path.segments.push(PathSegment::from(ident));
+    }
+    Some(path)
+}
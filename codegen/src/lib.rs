@@ -1,8 +1,18 @@
-use generate::generate_impl;
-use module::get_struct_from_path;
-use path::{get_root_src_path, parse_input_paths};
+use attributes::{
+    parse_field_options, parse_struct_options, strip_helper_attributes, StructOptions,
+};
+use generate::{generate_enum_impl, generate_impl, generate_partial, reverse_parameters};
+use generics::resolve_generics;
+use imports::{module_path_of_file, Imports};
+use module::{get_enum_from_path, get_struct_from_path};
+use path::{get_root_src_path, Arguments, PartialArguments, Target, TargetOptions};
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, Expr, ExprPath, ItemStruct};
+use quote::{quote, ToTokens};
+use std::path::PathBuf;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Field, Fields, Generics, Item, ItemEnum, ItemStruct, LitStr, Member, Path,
+};
 
 /// Helper macro, which attaches an error to a given span.
 macro_rules! err {
@@ -46,7 +56,10 @@ macro_rules! ok_or_err_return {
     }
 }
 
+mod attributes;
 mod generate;
+mod generics;
+mod imports;
 mod module;
 mod path;
 
@@ -55,9 +68,32 @@ mod path;
 /// Eiter a single struct or a list of structs can be provided.
 /// `StructMerge<T>` will then be implemented on each given target struct.
 ///
+/// Enums can be merged into other enums with variants of the same name.
+/// The fields of same variants are merged, while other variants replace the target.
+/// The target's variant can be kept via `#[merge(keep_variant)]` instead.
+///
 /// Examples:
 /// - `#[struct_merge(crate::structs::Target)]`
 /// - `#[struct_merge([crate::structs::Target, crate:structs::OtherTarget])]`
+/// - `#[struct_merge(crate::structs::GenericTarget<String>)]`
+/// - `#[struct_merge(crate::structs::Target, mode = "both")]`
+/// - `#[struct_merge([crate::structs::Target(strict), crate::structs::OtherTarget(skip(id))])]`
+///
+/// Generic parameters of the target struct can be bound to concrete arguments in the path.
+/// Unbound parameters become parameters of the generated impl. Parameters of the src and target
+/// struct with the same name are considered to be the same parameter.
+///
+/// `bidirectional` additionally implements `StructMerge<T>` on the src struct for each target,
+/// e.g. `#[struct_merge(crate::structs::Target, bidirectional)]`.
+/// The field options are reversed for these merges. Generic structs aren't supported.
+///
+/// `mode = "both"` additionally implements `StructMergeRef<S>`, just like `struct_merge_ref`.
+/// Each target is then only resolved once. The mode can be `owned`, `borrowed` or `both`.
+///
+/// Options of a single target are put in parentheses behind its path:
+/// - `strict`: All src fields must be merged into the target.
+/// - `skip(a, b)`: The given src fields aren't merged into the target.
+/// - `rename(a = b)`: The src field `a` is merged into the target field `b`.
 ///
 /// The targets struct paths have to be
 /// - absolute
@@ -88,7 +124,8 @@ pub fn struct_merge(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
 
 /// Implement the `struct_merge::StructMergeRef<S>` trait for all given targets.
 ///
-/// All fields to be merged must implement the [std::clone::Clone] trait.
+/// All fields to be merged must implement the [std::clone::Clone] trait, unless they're copied
+/// or converted via `#[merge(copy)]`, `#[merge(to_owned)]` or `#[merge(ref_with = "fn")]`.
 ///
 /// Eiter a single struct or a list of structs can be provided.
 /// `StructMergeRef<T>` will then be implemented on each given target struct.
@@ -96,6 +133,7 @@ pub fn struct_merge(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
 /// Examples:
 /// - `#[struct_merge_ref(crate::structs::Target)]`
 /// - `#[struct_merge_ref([crate::structs::Target, crate:structs::OtherTarget])]`
+/// - `#[struct_merge_ref(crate::structs::Target, bidirectional)]`
 ///
 /// The targets struct paths have to be
 /// - absolute
@@ -124,68 +162,636 @@ pub fn struct_merge_ref(args: TokenStream, struct_ast: TokenStream) -> TokenStre
     struct_merge_base(args, struct_ast, Mode::Borrowed)
 }
 
-/// This enum is used to differentiate between owned and borrowed merge behavior.
+/// Implement the `struct_merge::StructMergeTake<S>` trait for all given targets.
+///
+/// The values of the mutably borrowed src are moved into the target via [std::mem::take] and
+/// [Option::take]. All fields to be merged, which aren't optional, must implement the
+/// [std::default::Default] trait. Nested fields and arrays with optional elements aren't supported
+/// yet.
+///
+/// Eiter a single struct or a list of structs can be provided.
+/// `StructMergeTake<T>` will then be implemented on each given target struct.
+///
+/// Examples:
+/// - `#[struct_merge_take(crate::structs::Target)]`
+/// - `#[struct_merge_take([crate::structs::Target, crate:structs::OtherTarget])]`
+///
+/// The targets struct paths have to be
+/// - absolute
+/// - relative to the current crate
+/// - contained in this crate
+///
+/// `struct.rs`
+/// ```ignore
+/// use struct_merge::struct_merge_take;
+///
+/// pub struct Target {
+///     pub test: String,
+/// }
+///
+/// #[struct_merge_take(crate::structs::Target)]
+/// pub struct Test {
+///     pub test: Option<String>,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn struct_merge_take(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
+    struct_merge_base(args, struct_ast, Mode::Take)
+}
+
+/// Implement the `struct_merge::StructMerge3<S>` trait for all given targets.
+///
+/// All fields to be merged must implement the [std::clone::Clone] and [std::cmp::PartialEq]
+/// traits.
+///
+/// Eiter a single struct or a list of structs can be provided.
+/// `StructMerge3<T>` will then be implemented on each given target struct.
+///
+/// Examples:
+/// - `#[struct_merge3(crate::structs::Target)]`
+/// - `#[struct_merge3([crate::structs::Target, crate:structs::OtherTarget])]`
+///
+/// The targets struct paths have to be
+/// - absolute
+/// - relative to the current crate
+/// - contained in this crate
+///
+/// `struct.rs`
+/// ```ignore
+/// use struct_merge::struct_merge3;
+///
+/// pub struct Target {
+///     pub test: String,
+/// }
+///
+/// #[struct_merge3(crate::structs::Target)]
+/// pub struct Test {
+///     pub test: String,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn struct_merge3(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
+    struct_merge_base(args, struct_ast, Mode::ThreeWay)
+}
+
+/// Implement the `struct_merge::TryStructMerge<S>` trait for all given targets.
+///
+/// Values are converted via [std::convert::TryFrom], if the types of two fields differ.
+/// Fields can be validated via `#[merge(validate = "path::to::fn")]`, which is called with a
+/// reference to the converted value.
+/// The target can be validated after merging via the same attribute on the struct, which is
+/// called with a reference to the target.
+/// Validation functions return a `Result<(), E>`, where `E` can be converted into a
+/// `Box<dyn std::error::Error + Send + Sync>`.
+///
+/// If any conversion or validation fails, the target is left untouched.
+///
+/// The error type defaults to `struct_merge::MergeError`.
+/// A custom error type that implements `From<struct_merge::MergeError>` can be set via
+/// `#[merge(error = "path::to::Error")]`.
+///
+/// Eiter a single struct or a list of structs can be provided.
+/// `TryStructMerge<T>` will then be implemented on each given target struct.
+///
+/// Examples:
+/// - `#[try_struct_merge(crate::structs::Target)]`
+/// - `#[try_struct_merge([crate::structs::Target, crate:structs::OtherTarget])]`
+///
+/// The targets struct paths have to be
+/// - absolute
+/// - relative to the current crate
+/// - contained in this crate
+///
+/// `struct.rs`
+/// ```ignore
+/// use struct_merge::try_struct_merge;
+///
+/// pub struct Target {
+///     pub min: u8,
+///     pub max: u8,
+/// }
+///
+/// fn check_limits(target: &Target) -> Result<(), String> {
+///     if target.min > target.max {
+///         return Err("min must not be greater than max".to_string());
+///     }
+///     Ok(())
+/// }
+///
+/// #[try_struct_merge(crate::structs::Target)]
+/// #[merge(validate = "check_limits")]
+/// pub struct Test {
+///     pub min: u32,
+///     pub max: Option<u32>,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn try_struct_merge(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
+    struct_merge_base(args, struct_ast, Mode::Fallible)
+}
+
+/// Generate a partial struct of the target struct, whose fields are all wrapped in an `Option`.
+/// `struct_merge::StructMergeInto` and `struct_merge::StructMergeIntoRef` are implemented for
+/// the partial struct.
+///
+/// Fields that already are an `Option<T>` become an `Option<Option<T>>`, which can clear the
+/// target's field. Fields of nested structs can use the partial struct of their type via
+/// `#[merge(partial = "path::to::Partial")]`, which is then merged via `#[merge(nested)]`.
+///
+/// The partial struct has the same visibility and generics as the target.
+/// Doc comments of the fields are taken over. Derives and further attributes of the partial
+/// struct can be specified via `derive(...)` and `attr(...)`.
+///
+/// All fields must implement the [std::clone::Clone] trait. Generic parameters are only
+/// required to implement it for `StructMergeIntoRef`.
+///
+/// Examples:
+/// - `#[struct_merge_partial(name = "ConfigPatch")]`
+/// - `#[struct_merge_partial(name = "ConfigPatch", derive(Debug, Deserialize), attr(serde(default)))]`
+///
+/// `struct.rs`
+/// ```ignore
+/// use struct_merge::struct_merge_partial;
+///
+/// #[struct_merge_partial(name = "AddressPatch", derive(Debug))]
+/// pub struct Address {
+///     pub city: String,
+/// }
+///
+/// #[struct_merge_partial(name = "ConfigPatch", derive(Debug))]
+/// pub struct Config {
+///     pub name: String,
+///     pub description: Option<String>,
+///     #[merge(partial = "AddressPatch")]
+///     pub address: Address,
+/// }
+///
+/// // Generates
+/// /// A partial [Config], whose fields are all optional.
+/// #[derive(Debug)]
+/// pub struct ConfigPatch {
+///     pub name: Option<String>,
+///     pub description: Option<Option<String>>,
+///     pub address: Option<AddressPatch>,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn struct_merge_partial(args: TokenStream, struct_ast: TokenStream) -> TokenStream {
+    let args_tokens = proc_macro2::TokenStream::from(args.clone());
+    let parsed_args = parse_macro_input!(args as PartialArguments);
+    // Check if we can find the src root path of this crate.
+    // Return early if it doesn't exist.
+    let src_root_path = match get_root_src_path(&args_tokens) {
+        Some(path) => path,
+        None => return struct_ast,
+    };
+
+    let target_item = parse_macro_input!(struct_ast as Item);
+    let target_struct = match &target_item {
+        Item::Struct(target_struct) => target_struct.clone(),
+        _ => {
+            let error = err!(target_item, "struct_merge_partial only supports structs.");
+            return TokenStream::from(quote!(#target_item #error));
+        }
+    };
+
+    // The item is handed back to the compiler without our `#[merge(...)]` helper attributes.
+    let mut output_item = target_item.clone();
+    strip_helper_attributes(&mut output_item);
+    let mut output = TokenStream::from(output_item.to_token_stream());
+
+    let options = match parse_struct_options(&target_struct.attrs) {
+        Ok(options) => options,
+        Err(error) => {
+            output.extend(TokenStream::from(error));
+            return output;
+        }
+    };
+
+    // Check the field options once, so we don't have to handle errors in the generator.
+    for field in target_struct.fields.iter() {
+        if let Err(error) = parse_field_options(&field.attrs) {
+            output.extend(TokenStream::from(error));
+            return output;
+        }
+    }
+
+    // The partial struct is located in the same file as the target.
+    let imports = src_file_imports(&src_root_path);
+    let tokens = match generate_partial(&parsed_args, &target_struct, options, imports) {
+        Ok(tokens) => tokens,
+        Err(error) => error,
+    };
+    output.extend(TokenStream::from(tokens));
+
+    output
+}
+
+/// This enum is used to differentiate between the different merge behaviors.
 /// Depending on this, we need to generate another trait impl and slightly different code.
 enum Mode {
     Owned,
     Borrowed,
+    Take,
+    ThreeWay,
+    Fallible,
 }
 
+#[derive(Clone)]
 pub(crate) struct Parameters {
     pub src_struct: ItemStruct,
-    pub target_path: ExprPath,
+    /// The path to the src struct, if it isn't declared in the scope of the generated impls.
+    /// E.g. the target struct of a bidirectional merge, which becomes the src of the reversed
+    /// merge.
+    pub src_path: Option<Path>,
+    /// The path to the target struct, including all of its generic arguments.
+    pub target_path: Path,
+    /// The target struct, in which all generic parameters that are bound via the target path
+    /// have been substituted.
     pub target_struct: ItemStruct,
+    /// The generics of the generated impls.
+    pub generics: Generics,
+    pub options: StructOptions,
+    /// The options of this specific target, such as `strict`.
+    pub target_options: TargetOptions,
+    /// The imports of the src's file.
+    pub src_imports: Imports,
+    /// The imports of the target's file.
+    pub target_imports: Imports,
+}
+
+impl Parameters {
+    /// The type of the src struct, including all of its generic parameters.
+    pub fn src_type(&self) -> proc_macro2::TokenStream {
+        if let Some(src_path) = &self.src_path {
+            return quote!(#src_path);
+        }
+
+        let ident = &self.src_struct.ident;
+        let (_, ty_generics, _) = self.src_struct.generics.split_for_impl();
+        quote!(#ident #ty_generics)
+    }
+}
+
+/// The counterpart of [Parameters] for merging a src enum into a target enum.
+#[derive(Clone)]
+pub(crate) struct EnumParameters {
+    pub src_enum: ItemEnum,
+    /// The path to the target enum, including all of its generic arguments.
+    pub target_path: Path,
+    /// The target enum, in which all generic parameters that are bound via the target path
+    /// have been substituted.
+    pub target_enum: ItemEnum,
+    /// The generics of the generated impls.
+    pub generics: Generics,
+    pub options: StructOptions,
+    /// The options of this specific target, such as `strict`.
+    pub target_options: TargetOptions,
+    /// The imports of the src's file.
+    pub src_imports: Imports,
+    /// The imports of the target's file.
+    pub target_imports: Imports,
+}
+
+impl EnumParameters {
+    /// The type of the src enum, including all of its generic parameters.
+    pub fn src_type(&self) -> proc_macro2::TokenStream {
+        let ident = &self.src_enum.ident;
+        let (_, ty_generics, _) = self.src_enum.generics.split_for_impl();
+        quote!(#ident #ty_generics)
+    }
 }
 
-fn struct_merge_base(args: TokenStream, mut struct_ast: TokenStream, mode: Mode) -> TokenStream {
-    let parsed_args = parse_macro_input!(args as Expr);
+fn struct_merge_base(args: TokenStream, item_ast: TokenStream, mode: Mode) -> TokenStream {
+    let args_tokens = proc_macro2::TokenStream::from(args.clone());
+    let parsed_args = parse_macro_input!(args as Arguments);
     // Check if we can find the src root path of this crate.
     // Return early if it doesn't exist.
-    let src_root_path = match get_root_src_path(&parsed_args) {
+    let src_root_path = match get_root_src_path(&args_tokens) {
         Some(path) => path,
-        None => return struct_ast,
+        None => return item_ast,
     };
 
-    // Parse the main macro input as a struct.
-    // We work on a clone of the struct ast.
-    // That way we don't have to parse it lateron when we return it.
-    let cloned_struct_ast = struct_ast.clone();
-    let src_struct = parse_macro_input!(cloned_struct_ast as ItemStruct);
+    // Parse the main macro input as a struct or an enum.
+    let src_item = parse_macro_input!(item_ast as Item);
+    let (attrs, fields): (_, Vec<&Field>) = match &src_item {
+        Item::Struct(src_struct) => (&src_struct.attrs, src_struct.fields.iter().collect()),
+        Item::Enum(src_enum) => (
+            &src_enum.attrs,
+            src_enum
+                .variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter())
+                .collect(),
+        ),
+        _ => {
+            let error = err!(src_item, "struct_merge only supports structs and enums.");
+            return TokenStream::from(quote!(#src_item #error));
+        }
+    };
 
-    // Get the input paths from the given argument expressions.
-    let paths = parse_input_paths(parsed_args);
+    // The item is handed back to the compiler without our `#[merge(...)]` helper attributes.
+    let mut output_item = src_item.clone();
+    strip_helper_attributes(&mut output_item);
+    let mut output = TokenStream::from(output_item.to_token_stream());
 
-    // Go through all paths and process the respective struct.
-    let mut impls = Vec::new();
-    for target_path in paths {
-        // Make sure we found the struct at that path.
-        let target_struct = match get_struct_from_path(src_root_path.clone(), target_path.clone()) {
-            Ok(ast) => ast,
+    let options = match parse_struct_options(attrs) {
+        Ok(options) => options,
+        Err(error) => {
+            output.extend(TokenStream::from(error));
+            return output;
+        }
+    };
+
+    // Check the field options once, so we don't have to handle errors in the generators.
+    for field in fields {
+        if let Err(error) = parse_field_options(&field.attrs) {
+            output.extend(TokenStream::from(error));
+            return output;
+        }
+    }
+
+    let modes = match parse_modes(mode, parsed_args.mode.as_ref()) {
+        Ok(modes) => modes,
+        Err(error) => {
+            output.extend(TokenStream::from(error));
+            return output;
+        }
+    };
+
+    // The imports of the src's file are needed to compare the types of src and target fields.
+    let src_imports = src_file_imports(&src_root_path);
+
+    // Bidirectional merges need the path of the src struct, as it's the target of the reverse
+    // merge.
+    let src_path = if parsed_args.bidirectional {
+        match bidirectional_src_path(&modes, &src_item, &src_root_path) {
+            Ok(src_path) => Some(src_path),
             Err(error) => {
-                impls.push(error);
-                continue;
+                output.extend(TokenStream::from(error));
+                return output;
             }
-        };
+        }
+    } else {
+        None
+    };
 
-        let params = Parameters {
-            src_struct: src_struct.clone(),
-            target_path,
-            target_struct,
+    // Go through all paths and process the respective struct or enum.
+    let mut impls = Vec::new();
+    for target in parsed_args.targets {
+        if let Err(error) = check_target_options(&target.options, &src_item) {
+            impls.push(error);
+            continue;
+        }
+
+        let result = match &src_item {
+            Item::Struct(src_struct) => struct_impl(
+                &modes,
+                src_struct,
+                src_root_path.clone(),
+                target,
+                &options,
+                &src_imports,
+                src_path.as_ref(),
+            ),
+            Item::Enum(src_enum) => enum_impl(
+                &modes,
+                src_enum,
+                src_root_path.clone(),
+                target,
+                &options,
+                &src_imports,
+            ),
+            _ => unreachable!(),
         };
 
-        // Generate the MergeStruct trait implementations.
-        match generate_impl(&mode, params) {
+        // Errors are added to the output as well.
+        match result {
             Ok(ast) => impls.push(ast),
-            Err(error) => {
-                impls.push(error);
-                continue;
-            }
+            Err(error) => impls.push(error),
         }
     }
 
-    // Merge all generated pieces of the code with the original unaltered struct.
-    struct_ast.extend(impls.into_iter().map(TokenStream::from));
+    // Merge all generated pieces of the code with the original item.
+    output.extend(impls.into_iter().map(TokenStream::from));
 
     // Hand the final output tokens back to the compiler.
-    struct_ast
+    output
+}
+
+/// Collect the imports of the file, in which the current macro is invoked.
+fn src_file_imports(src_root_path: &std::path::Path) -> Imports {
+    match proc_macro::Span::call_site().local_file() {
+        Some(file_path) => {
+            let module = module_path_of_file(src_root_path, &file_path);
+            Imports::from_file(&file_path, module.as_ref())
+        }
+        None => Imports::default(),
+    }
+}
+
+/// Check that all src fields, which are referred to by the options of a target, exist.
+/// The fields of enums may exist in any of their variants.
+fn check_target_options(
+    options: &TargetOptions,
+    src_item: &Item,
+) -> Result<(), proc_macro2::TokenStream> {
+    let field_sets: Vec<&Fields> = match src_item {
+        Item::Struct(src_struct) => vec![&src_struct.fields],
+        Item::Enum(src_enum) => src_enum
+            .variants
+            .iter()
+            .map(|variant| &variant.fields)
+            .collect(),
+        _ => Vec::new(),
+    };
+    let exists = |member: &Member| {
+        field_sets.iter().any(|fields| {
+            fields
+                .iter()
+                .enumerate()
+                .any(|(index, field)| match (member, &field.ident) {
+                    (Member::Named(name), Some(ident)) => name == ident,
+                    (Member::Unnamed(member), None) => member.index as usize == index,
+                    _ => false,
+                })
+        })
+    };
+
+    let mut errors = proc_macro2::TokenStream::new();
+    let members = options
+        .skip
+        .iter()
+        .chain(options.rename.iter().map(|(from, _)| from));
+    for member in members {
+        if !exists(member) {
+            errors.extend(vec![err!(
+                member,
+                "The src doesn't have a field '{}'.",
+                member.to_token_stream()
+            )]);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
+
+/// Determine the merges to generate via the `mode = "..."` parameter.
+///
+/// The mode can only be set for `struct_merge`, which otherwise generates owned merges.
+fn parse_modes(
+    mode: Mode,
+    argument: Option<&LitStr>,
+) -> Result<Vec<Mode>, proc_macro2::TokenStream> {
+    let argument = match argument {
+        Some(argument) => argument,
+        None => return Ok(vec![mode]),
+    };
+    if !matches!(mode, Mode::Owned) {
+        return Err(err!(argument, "The mode can only be set for struct_merge."));
+    }
+
+    match argument.value().as_str() {
+        "owned" => Ok(vec![Mode::Owned]),
+        "borrowed" => Ok(vec![Mode::Borrowed]),
+        "both" => Ok(vec![Mode::Owned, Mode::Borrowed]),
+        _ => Err(err!(
+            argument,
+            "Unknown mode. Expected 'owned', 'borrowed' or 'both'."
+        )),
+    }
+}
+
+/// Get the path of the src struct for bidirectional merges, e.g. `crate::structs::Src`.
+///
+/// Only owned and borrowed merges of structs can be bidirectional.
+fn bidirectional_src_path(
+    modes: &[Mode],
+    src_item: &Item,
+    src_root_path: &std::path::Path,
+) -> Result<Path, proc_macro2::TokenStream> {
+    let src_struct = match src_item {
+        Item::Struct(src_struct) => src_struct,
+        _ => {
+            return Err(err!(
+                src_item,
+                "Bidirectional merges aren't supported for enums yet."
+            ))
+        }
+    };
+    if modes
+        .iter()
+        .any(|mode| !matches!(mode, Mode::Owned | Mode::Borrowed))
+    {
+        return Err(err!(
+            src_struct.ident,
+            "Only struct_merge and struct_merge_ref support bidirectional merges."
+        ));
+    }
+
+    let module = proc_macro::Span::call_site()
+        .local_file()
+        .and_then(|file_path| module_path_of_file(src_root_path, &file_path));
+    match module {
+        Some(mut path) => {
+            path.segments
+                .push(syn::PathSegment::from(src_struct.ident.clone()));
+            Ok(path)
+        }
+        None => Err(err!(
+            src_struct.ident,
+            "Couldn't determine the module of {} for the bidirectional merge.",
+            src_struct.ident
+        )),
+    }
+}
+
+/// Generate the impls of all `modes` to merge the src struct into the target struct at the given
+/// path. The target struct is only resolved once for all of them.
+///
+/// If the `src_path` is given, the impls to merge the target struct back into the src struct
+/// are generated as well.
+fn struct_impl(
+    modes: &[Mode],
+    src_struct: &ItemStruct,
+    src_root_path: PathBuf,
+    target: Target,
+    options: &StructOptions,
+    src_imports: &Imports,
+    src_path: Option<&Path>,
+) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    let mut target_path = target.path;
+    // Make sure we found the struct at that path.
+    let (mut target_struct, target_imports) = get_struct_from_path(src_root_path, &target_path)?;
+
+    // Bind the generic parameters of the target and determine the generics of the impls.
+    let generics = resolve_generics(&src_struct.generics, &mut target_path, &mut target_struct)?;
+
+    let params = Parameters {
+        src_struct: src_struct.clone(),
+        src_path: None,
+        target_path,
+        target_struct,
+        generics,
+        options: options.clone(),
+        target_options: target.options,
+        src_imports: src_imports.clone(),
+        target_imports,
+    };
+
+    let reversed = match src_path {
+        Some(src_path) => Some(reverse_parameters(&params, src_path.clone())?),
+        None => None,
+    };
+
+    // Generate the MergeStruct trait implementations.
+    let mut tokens = proc_macro2::TokenStream::new();
+    for mode in modes {
+        tokens.extend(vec![generate_impl(mode, params.clone())?]);
+        if let Some(reversed) = &reversed {
+            tokens.extend(vec![generate_impl(mode, reversed.clone())?]);
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Generate the impls of all `modes` to merge the src enum into the target enum at the given
+/// path.
+fn enum_impl(
+    modes: &[Mode],
+    src_enum: &ItemEnum,
+    src_root_path: PathBuf,
+    target: Target,
+    options: &StructOptions,
+    src_imports: &Imports,
+) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    let mut target_path = target.path;
+    // Make sure we found the enum at that path.
+    let (mut target_enum, target_imports) = get_enum_from_path(src_root_path, &target_path)?;
+
+    // Bind the generic parameters of the target and determine the generics of the impls.
+    let generics = resolve_generics(&src_enum.generics, &mut target_path, &mut target_enum)?;
+
+    let params = EnumParameters {
+        src_enum: src_enum.clone(),
+        target_path,
+        target_enum,
+        generics,
+        options: options.clone(),
+        target_options: target.options,
+        src_imports: src_imports.clone(),
+        target_imports,
+    };
+
+    let mut tokens = proc_macro2::TokenStream::new();
+    for mode in modes {
+        tokens.extend(vec![generate_enum_impl(mode, params.clone())?]);
+    }
+
+    Ok(tokens)
 }
@@ -1,19 +1,89 @@
 use std::path::PathBuf;
 
 use proc_macro2::TokenStream;
-use syn::{spanned::Spanned, ExprPath, Ident, Item, ItemStruct, Token};
+use syn::{spanned::Spanned, Ident, Item, ItemEnum, ItemStruct, Path, PathSegment, Token};
 
-/// This function takes a path to a struct and returns the AST of that struct.
+use crate::imports::Imports;
+
+/// This function takes a path to a struct and returns the AST of that struct and the imports of
+/// its file.
 ///
 /// There is no easy way to do module resolution during this stage of the compilation.
 pub fn get_struct_from_path(
+    file_path: PathBuf,
+    path: &Path,
+) -> Result<(ItemStruct, Imports), TokenStream> {
+    let (items, name, file_path) = get_items_from_path(file_path, path)?;
+    let imports = get_imports(&items, path);
+
+    for item in items.into_iter() {
+        if let Item::Struct(item_struct) = item {
+            if item_struct.ident == name {
+                return Ok((item_struct, imports));
+            }
+        }
+    }
+
+    Err(err!(
+        path,
+        "Didn't find struct {} in file {:?}",
+        name,
+        &file_path
+    ))
+}
+
+/// This function takes a path to an enum and returns the AST of that enum and the imports of its
+/// file.
+pub fn get_enum_from_path(
+    file_path: PathBuf,
+    path: &Path,
+) -> Result<(ItemEnum, Imports), TokenStream> {
+    let (items, name, file_path) = get_items_from_path(file_path, path)?;
+    let imports = get_imports(&items, path);
+
+    for item in items.into_iter() {
+        if let Item::Enum(item_enum) = item {
+            if item_enum.ident == name {
+                return Ok((item_enum, imports));
+            }
+        }
+    }
+
+    Err(err!(
+        path,
+        "Didn't find enum {} in file {:?}",
+        name,
+        &file_path
+    ))
+}
+
+/// Collect the imports of the file, that contains the item at the given path.
+/// The module of that file is the item's path without its last segment.
+fn get_imports(items: &[Item], path: &Path) -> Imports {
+    let mut module = Path {
+        leading_colon: None,
+        segments: Default::default(),
+    };
+    for segment in path.segments.iter().take(path.segments.len() - 1) {
+        module
+            .segments
+            .push(PathSegment::from(segment.ident.clone()));
+    }
+
+    Imports::new(items, Some(&module))
+}
+
+/// Find and parse the file of the module that contains the item at the given path.
+///
+/// Returns all items of that file, the name of the item and the path to the file.
+fn get_items_from_path(
     mut file_path: PathBuf,
-    path: ExprPath,
-) -> Result<ItemStruct, TokenStream> {
+    path: &Path,
+) -> Result<(Vec<Item>, Ident, PathBuf), TokenStream> {
     // Start searching for files from the project root.
     let path_span = path.span();
 
-    let mut segments = path.path.segments.into_iter().peekable();
+    let mut segments = path.segments.iter().peekable();
     // Make sure the root of the path is the current crate.
     let first = segments.next().unwrap();
     let crate_token = Token![crate](first.span());
@@ -26,15 +96,15 @@ pub fn get_struct_from_path(
     }
 
     // Get the file path for the specified Rust path.
-    let target_struct_name = loop {
+    let target_name = loop {
         // We know that the next value exists.
         // If no further value exists, we break and exit early.
         let segment = segments.next().unwrap();
 
-        // The last identifier is the the name of the struct.
+        // The last identifier is the the name of the item.
         // Break, so it doen't get added to the path.
         if segments.peek().is_none() {
-            break segment.ident;
+            break &segment.ident;
         }
 
         // Push the next identifier to the path.
@@ -68,18 +138,5 @@ pub fn get_struct_from_path(
         "Failed to parse file: {:?}"
     );
 
-    for item in file_ast.items.into_iter() {
-        if let Item::Struct(item_struct) = item {
-            if item_struct.ident == target_struct_name {
-                return Ok(item_struct);
-            }
-        }
-    }
-
-    Err(err!(
-        path_span,
-        "Didn't find struct {} in file {:?}",
-        target_struct_name,
-        &file_path
-    ))
+    Ok((file_ast.items, target_name.clone(), file_path))
 }
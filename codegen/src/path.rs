@@ -1,38 +1,249 @@
 use std::path::PathBuf;
 
-use syn::{spanned::Spanned, Expr, ExprPath};
+use proc_macro2::TokenStream;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    bracketed, parenthesized, spanned::Spanned, token, Ident, LitStr, Member, Path, PathArguments,
+    PathSegment, Token,
+};
 
-/// Extract the input paths from the macro arguments.
+/// The parsed arguments of this crate's macros.
 ///
 /// Both, a single path and an array of paths is supported.
+/// Generic arguments of the target structs can be specified as well, just like options for
+/// single targets. See [TargetOptions].
+/// The paths can be followed by further parameters:
+/// - `bidirectional`, to merge the targets into the src as well.
+/// - `mode = "both"`, to generate the owned and the borrowed merges at once.
+///
 /// I.e.
-/// - `merge_struct(crate::some::path)`
-/// - `merge_struct([crate::some::struct, crate::some_other::struct])`
-pub fn parse_input_paths(args: Expr) -> Vec<ExprPath> {
-    match args {
-        Expr::Path(path) => {
-            vec![path]
+/// - `struct_merge(crate::some::path)`
+/// - `struct_merge([crate::some::struct, crate::some_other::struct])`
+/// - `struct_merge(crate::some::Generic<String>)`
+/// - `struct_merge(crate::some::path, bidirectional)`
+/// - `struct_merge(crate::some::path, mode = "both")`
+/// - `struct_merge([crate::some::path(strict), crate::other::path(skip(id))])`
+pub struct Arguments {
+    pub targets: Vec<Target>,
+    pub bidirectional: bool,
+    /// The merges to generate, i.e. `owned`, `borrowed` or `both`.
+    /// Defaults to the merge of the respective macro.
+    pub mode: Option<LitStr>,
+}
+
+impl Parse for Arguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let targets = if input.peek(token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            let targets = Punctuated::<Target, Token![,]>::parse_terminated(&content)?;
+            targets.into_iter().collect()
+        } else {
+            vec![input.parse()?]
+        };
+
+        let mut bidirectional = false;
+        let mut mode = None;
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            let key: Ident = input.parse()?;
+            if key == "bidirectional" {
+                bidirectional = true;
+            } else if key == "mode" {
+                input.parse::<Token![=]>()?;
+                mode = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "Unknown struct_merge parameter. Expected 'bidirectional' or 'mode = \"...\"'.",
+                ));
+            }
+        }
+
+        if !input.is_empty() {
+            return Err(input.error(
+                "struct_merge's macro parameters should be either a single path \
+                or a vector of paths, such as '[crate::your::path]'.",
+            ));
+        }
+
+        Ok(Arguments {
+            targets,
+            bidirectional,
+            mode,
+        })
+    }
+}
+
+/// A single target of a merge, i.e. its path and the options for this target.
+pub struct Target {
+    pub path: Path,
+    pub options: TargetOptions,
+}
+
+impl Parse for Target {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = parse_target_path(input)?;
+
+        let options = if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            content.parse()?
+        } else {
+            TargetOptions::default()
+        };
+
+        Ok(Target { path, options })
+    }
+}
+
+/// Parse the path of a target, such as `crate::some::Generic<String>`.
+///
+/// [Path]'s own parser would treat trailing options, such as `Target(strict)`, as the arguments
+/// of a function trait, so the segments are parsed by hand.
+fn parse_target_path(input: ParseStream) -> syn::Result<Path> {
+    let leading_colon = input.parse()?;
+    let mut segments = Punctuated::new();
+    loop {
+        // `crate`, `self` and `super` are keywords.
+        let ident = input.call(Ident::parse_any)?;
+        let arguments = if input.peek(Token![<]) {
+            PathArguments::AngleBracketed(input.parse()?)
+        } else {
+            PathArguments::None
+        };
+        segments.push_value(PathSegment { ident, arguments });
+
+        if !input.peek(Token![::]) {
+            break;
         }
-        Expr::Array(array) => {
-            let mut paths = vec![];
-            for expr in array.elems {
-                match expr {
-                    Expr::Path(path) => paths.push(path),
-                    _ => {
-                        err!(expr, "Only paths are allowed in struct_merge's attribute.");
+        segments.push_punct(input.parse()?);
+    }
+
+    Ok(Path {
+        leading_colon,
+        segments,
+    })
+}
+
+/// Options of a single target, which are put in parentheses behind the target's path.
+/// All fields are referred to by their name or index on the src.
+/// I.e.
+/// - `crate::some::path(strict)`: All src fields must be merged into the target.
+///   Src fields without a respective target field result in errors.
+/// - `crate::some::path(skip(id, created))`: These src fields aren't merged into the target.
+/// - `crate::some::path(rename(name = title))`: The src field `name` is merged into the target's
+///   `title` field. This takes precedence over `#[merge(name = "...")]`.
+#[derive(Clone, Default)]
+pub struct TargetOptions {
+    pub strict: bool,
+    pub skip: Vec<Member>,
+    pub rename: Vec<(Member, Member)>,
+}
+
+impl Parse for TargetOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = TargetOptions::default();
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "strict" {
+                options.strict = true;
+            } else if key == "skip" {
+                let content;
+                parenthesized!(content in input);
+                let members = Punctuated::<Member, Token![,]>::parse_terminated(&content)?;
+                options.skip.extend(members);
+            } else if key == "rename" {
+                let content;
+                parenthesized!(content in input);
+                while !content.is_empty() {
+                    let from: Member = content.parse()?;
+                    content.parse::<Token![=]>()?;
+                    let to: Member = content.parse()?;
+                    options.rename.push((from, to));
+
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
                     }
                 }
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "Unknown target option. Expected 'strict', 'skip(...)' or 'rename(...)'.",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The parsed arguments of the `struct_merge_partial` macro.
+///
+/// The name of the partial struct is required, all other arguments are optional.
+/// I.e.
+/// - `struct_merge_partial(name = "ConfigPatch")`
+/// - `struct_merge_partial(name = "ConfigPatch", derive(Debug, Clone))`
+/// - `struct_merge_partial(name = "ConfigPatch", attr(serde(default)))`
+pub struct PartialArguments {
+    pub name: Ident,
+    /// The derives of the partial struct.
+    pub derives: Vec<Path>,
+    /// Further attributes of the partial struct, without the surrounding `#[...]`.
+    pub attrs: Vec<TokenStream>,
+}
+
+impl Parse for PartialArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        let mut derives = Vec::new();
+        let mut attrs = Vec::new();
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "name" {
+                input.parse::<Token![=]>()?;
+                let lit: LitStr = input.parse()?;
+                name = Some(lit.parse()?);
+            } else if key == "derive" {
+                let content;
+                parenthesized!(content in input);
+                let paths = Punctuated::<Path, Token![,]>::parse_terminated(&content)?;
+                derives.extend(paths);
+            } else if key == "attr" {
+                let content;
+                parenthesized!(content in input);
+                attrs.push(content.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "Unknown struct_merge_partial parameter. \
+                    Expected 'name = \"...\"', 'derive(...)' or 'attr(...)'.",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
             }
-            paths
         }
-        _ => {
-            err!(
-                args,
-                "struct_merge's macro parameters should be either a single path {} ",
-                "or a vector of paths, such as '[crate::your::path]'."
-            );
 
-            vec![]
+        match name {
+            Some(name) => Ok(PartialArguments {
+                name,
+                derives,
+                attrs,
+            }),
+            None => Err(input.error(
+                "struct_merge_partial needs the name of the partial struct, \
+                such as 'name = \"ConfigPatch\"'.",
+            )),
         }
     }
 }
@@ -40,7 +251,7 @@ pub fn parse_input_paths(args: Expr) -> Vec<ExprPath> {
 /// Get the root path of the crate that's currently using this proc macro.
 /// This is done via the `CARGO_MANIFEST_DIR` variable, that's always supplied by cargo and
 /// represents the directory containing the `Cargo.toml` for the current crate.
-pub fn get_root_src_path(parsed_args: &Expr) -> Option<PathBuf> {
+pub fn get_root_src_path(parsed_args: &impl Spanned) -> Option<PathBuf> {
     match std::env::var("CARGO_MANIFEST_DIR") {
         Err(error) => {
             err!(
@@ -1,12 +1,45 @@
+mod patches;
 mod structs;
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use struct_merge::merge_chain;
 use struct_merge::prelude::*;
 
+use crate::patches::*;
 use crate::structs::*;
 
 fn main() {
     merge();
     merge_soft();
+    merge3();
+    merge_report();
+    merge_preview();
+    try_merge();
+    merged();
+    merge_generic();
+    merge_tuple();
+    merge_enum();
+    merge_option_paths();
+    merge_aliased();
+    merge_shapes();
+    merge_pointers();
+    merge_imports();
+    merge_deferred();
+    merge_patch();
+    merge_partial();
+    extract();
+    diff();
+    merge_bidirectional();
+    merge_modes();
+    merge_target_options();
+    merge_references();
+    merge_take();
+    merge_layers();
+    merge_tracked();
 }
 
 /// Test the normal [StructMerge::merge] and [StructMergeRef::merge_soft] functions.
@@ -75,3 +108,1371 @@ fn merge_soft() {
     assert_eq!(base.optional, Some("mixed".to_string()));
     assert_eq!(base.ignored, "base".to_string());
 }
+
+/// Test the [StructMerge3::merge3] function.
+///
+/// Only fields that have been changed by the src struct will be merged.
+/// Fields that have been changed on both sides are reported as conflicts.
+fn merge3() {
+    // The common ancestor of both sides.
+    let base = Revision {
+        normal: "base".to_string(),
+        optional: Some("base".to_string()),
+    };
+
+    // Nothing changed on their side, so nothing will be merged.
+    let mut target = Base::new();
+    target.normal = "ours".to_string();
+    let conflicts = target.merge3(&base, &base);
+    assert!(conflicts.is_empty());
+    assert_eq!(target.normal, "ours".to_string());
+    assert_eq!(target.optional, Some("base".to_string()));
+
+    // `optional` only changed on their side, `normal` changed on both sides.
+    let theirs = Revision::new();
+    let conflicts = target.merge3(&base, &theirs);
+    assert_eq!(conflicts, vec!["normal"]);
+    assert_eq!(target.normal, "ours".to_string());
+    assert_eq!(target.optional, Some("revision".to_string()));
+    assert_eq!(target.ignored, "base".to_string());
+
+    // Both sides changed a field to the same value, which isn't a conflict.
+    let mut target = Base::new();
+    target.normal = "revision".to_string();
+    let conflicts = target.merge3(&base, &theirs);
+    assert!(conflicts.is_empty());
+    assert_eq!(target.normal, "revision".to_string());
+
+    // Optional src fields are only merged, if they're `Some`.
+    let base = OptionalRevision {
+        normal: "base".to_string(),
+        optional: None,
+    };
+    let theirs = OptionalRevision {
+        normal: "base".to_string(),
+        optional: Some(None),
+    };
+    let mut target = Base::new();
+    let conflicts = target.merge3(&base, &theirs);
+    // The base didn't have a value, so we cannot know whether ours has been changed.
+    assert_eq!(conflicts, vec!["optional"]);
+    assert_eq!(target.optional, Some("base".to_string()));
+
+    let base = OptionalRevision {
+        normal: "base".to_string(),
+        optional: Some(Some("base".to_string())),
+    };
+    let conflicts = target.merge3(&base, &theirs);
+    assert!(conflicts.is_empty());
+    assert_eq!(target.optional, None);
+}
+
+/// Test the [StructMerge::merge_report] and [StructMergeRef::merge_ref_report] functions and
+/// their soft counterparts.
+fn merge_report() {
+    let mut base = Base::new();
+
+    // `Mixed` doesn't track changes, so only written and skipped fields are recorded.
+    let mut mixed = Mixed::new();
+    mixed.optional = None;
+    let report = base.merge_report(mixed);
+    assert_eq!(report.written, vec!["normal"]);
+    assert_eq!(report.skipped, vec!["optional"]);
+    assert!(report.changed.is_empty());
+    assert_eq!(base.normal, "mixed".to_string());
+
+    // Soft merges skip all non-optional fields and all fields that are already `Some`.
+    let report = base.merge_soft_report(Mixed::new());
+    assert!(!report.is_written());
+    assert_eq!(report.skipped, vec!["normal", "optional"]);
+
+    // `Tracking` tracks changes, so we know which fields actually changed their value.
+    let mut base = Base::new();
+    base.normal = "tracking".to_string();
+    let report = base.merge_ref_report(&Tracking::new());
+    assert_eq!(report.written, vec!["normal", "optional"]);
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.changed, vec!["optional"]);
+
+    // Nothing changes the second time.
+    let report = base.merge_ref_report(&Tracking::new());
+    assert!(report.is_written());
+    assert!(!report.is_changed());
+
+    base.optional = None;
+    let report = base.merge_ref_soft_report(&Tracking::new());
+    assert_eq!(report.written, vec!["optional"]);
+    assert_eq!(report.skipped, vec!["normal"]);
+    assert_eq!(report.changed, vec!["optional"]);
+}
+
+/// Test the [StructMergePreview::merge_preview] function.
+fn merge_preview() {
+    let base = Base::new();
+    let preview = Preview::new();
+
+    let diff = base.merge_preview(&preview);
+    assert_eq!(diff.fields.len(), 2);
+    let normal = diff.field("normal").unwrap();
+    assert_eq!(normal.old, "\"base\"");
+    assert_eq!(normal.new, "\"preview\"");
+    assert_eq!(
+        diff.to_string(),
+        "normal  : \"base\" -> \"preview\"\noptional: Some(\"base\") -> Some(\"preview\")\n"
+    );
+
+    // The target isn't touched by a preview.
+    assert_eq!(base.normal, "base".to_string());
+
+    // Fields that wouldn't change aren't part of the diff.
+    let mut base = Base::new();
+    base.merge_ref(&preview);
+    let diff = base.merge_preview(&preview);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "No changes\n");
+}
+
+/// Test the [TryStructMerge::try_merge] function.
+///
+/// The target must be untouched, if any conversion or validation fails.
+fn try_merge() {
+    let mut limits = Limits::default();
+
+    let patch = LimitsPatch {
+        min: Some(10),
+        max: 20,
+        label: "limits".to_string(),
+    };
+    limits.try_merge(patch).unwrap();
+    assert_eq!(
+        limits,
+        Limits {
+            min: 10,
+            max: 20,
+            label: Some("limits".to_string()),
+            r#type: 0,
+        }
+    );
+
+    // `min` doesn't fit into a `u8`.
+    let patch = LimitsPatch {
+        min: Some(300),
+        max: 30,
+        label: "changed".to_string(),
+    };
+    let error = limits.try_merge(patch).unwrap_err();
+    assert_eq!(error.field(), Some("min"));
+    assert_eq!(limits.max, 20);
+
+    // The label is validated.
+    let patch = LimitsPatch {
+        min: None,
+        max: 30,
+        label: String::new(),
+    };
+    let error = limits.try_merge(patch).unwrap_err();
+    assert_eq!(error.field(), Some("label"));
+    assert_eq!(
+        error.to_string(),
+        "Failed to merge field 'label': label is empty".to_string()
+    );
+    assert_eq!(limits.max, 20);
+
+    // The merged target is invalid, as `min` is greater than `max`.
+    let patch = LimitsPatch {
+        min: None,
+        max: 5,
+        label: "changed".to_string(),
+    };
+    let error = limits.try_merge(patch).unwrap_err();
+    assert_eq!(error.field(), None);
+    assert_eq!(
+        limits,
+        Limits {
+            min: 10,
+            max: 20,
+            label: Some("limits".to_string()),
+            r#type: 0,
+        }
+    );
+
+    // Custom errors are created from a `MergeError`.
+    let error = limits.try_merge(MaxPatch { max: 256 }).unwrap_err();
+    assert_eq!(error.0.field(), Some("max"));
+    limits.try_merge(MaxPatch { max: 255 }).unwrap();
+    assert_eq!(limits.max, 255);
+
+    // Raw identifiers are merged just like any other field.
+    let error = limits.try_merge(TypePatch { r#type: 256 }).unwrap_err();
+    assert_eq!(error.field(), Some("type"));
+    limits.try_merge(TypePatch { r#type: 1 }).unwrap();
+    assert_eq!(limits.r#type, 1);
+}
+
+/// Test the non-mutating [StructMerge::merged] and [StructMergeRef::merged_ref] functions and
+/// their consuming counterparts.
+fn merged() {
+    let base = Base::new();
+
+    // The original struct is left untouched.
+    let merged = base.merged(Mixed::new());
+    assert_eq!(merged.normal, "mixed".to_string());
+    assert_eq!(base.normal, "base".to_string());
+
+    let merged = base.merged_ref(&Identical::new());
+    assert_eq!(merged.normal, "identical".to_string());
+    assert_eq!(merged.optional, Some("identical".to_string()));
+    assert_eq!(base.normal, "base".to_string());
+
+    // Soft merges only touch optional fields that are `None`.
+    let merged = base.merged_soft(Mixed::new());
+    assert_eq!(merged.normal, "base".to_string());
+    assert_eq!(merged.optional, Some("base".to_string()));
+
+    let mut empty = Base::new();
+    empty.optional = None;
+    let merged = empty.merged_ref_soft(&Identical::new());
+    assert_eq!(merged.normal, "base".to_string());
+    assert_eq!(merged.optional, Some("identical".to_string()));
+
+    // The consuming variants don't need `Clone`.
+    let merged = Base::new()
+        .into_merged(Mixed::new())
+        .into_merged_ref(&Identical::new());
+    assert_eq!(merged.normal, "identical".to_string());
+
+    let merged = empty.into_merged_soft(Mixed::new());
+    assert_eq!(merged.optional, Some("mixed".to_string()));
+}
+
+/// Test merging of generic structs.
+fn merge_generic() {
+    let mut target = GenericTarget {
+        value: 1,
+        optional: None,
+        label: None,
+    };
+
+    let patch = GenericPatch {
+        value: None,
+        optional: 2,
+        label: Some("patch"),
+    };
+    target.merge_ref(&patch);
+    assert_eq!(target.value, 1);
+    assert_eq!(target.optional, Some(2));
+    assert_eq!(target.label, Some("patch"));
+
+    target.merge(GenericPatch {
+        value: Some(3),
+        optional: 4,
+        label: None,
+    });
+    assert_eq!(target.value, 3);
+    assert_eq!(target.optional, Some(4));
+    assert_eq!(target.label, None);
+
+    let mut target = GenericTarget {
+        value: "target".to_string(),
+        optional: None,
+        label: None,
+    };
+    target.merge(StringPatch {
+        value: "patch".to_string(),
+        label: Some("patch"),
+    });
+    assert_eq!(target.value, "patch".to_string());
+    assert_eq!(target.label, Some("patch"));
+}
+
+fn merge_tuple() {
+    let mut target = Point(1, 2, None);
+    target.merge_ref(&PointPatch(None, 3, "patch".to_string()));
+    assert_eq!(target.0, 1);
+    assert_eq!(target.1, 3);
+    assert_eq!(target.2, Some("patch".to_string()));
+
+    let report = target.merge_report(PointPatch(Some(4), 5, "patch".to_string()));
+    assert_eq!(report.written, vec!["0", "1", "2"]);
+    assert_eq!(target.0, 4);
+    assert_eq!(target.1, 5);
+
+    target.merge(NamedPoint { x: 6, y: None });
+    assert_eq!(target.0, 6);
+    assert_eq!(target.1, 5);
+
+    let mut base = Base::new();
+    base.merge_ref(&BaseTuple("tuple".to_string(), None));
+    assert_eq!(base.normal, "tuple".to_string());
+    assert_eq!(base.optional, None);
+}
+
+fn merge_enum() {
+    let mut target = Backend::S3 {
+        bucket: "bucket".to_string(),
+        region: None,
+    };
+
+    // Same variants are merged field by field.
+    target.merge(BackendPatch::S3 {
+        bucket: None,
+        region: "eu".to_string(),
+    });
+    assert_eq!(
+        target,
+        Backend::S3 {
+            bucket: "bucket".to_string(),
+            region: Some("eu".to_string()),
+        }
+    );
+
+    let report = target.merge_ref_report(&BackendPatch::S3 {
+        bucket: Some("other".to_string()),
+        region: "us".to_string(),
+    });
+    assert_eq!(report.written, vec!["bucket", "region"]);
+    assert_eq!(report.changed, vec!["bucket", "region"]);
+    assert_eq!(report.variant, None);
+
+    // Different variants replace the target, which writes all of their fields.
+    let report = target.merge_report(BackendPatch::Memory(5));
+    assert_eq!(target, Backend::Memory(5));
+    assert_eq!(report.variant, Some("Memory"));
+    assert_eq!(report.written, vec!["0"]);
+    assert_eq!(report.changed, vec!["0"]);
+
+    target.merge_ref(&BackendPatch::Local {
+        path: "/".to_string(),
+    });
+    assert_eq!(
+        target,
+        Backend::Local {
+            path: "/".to_string()
+        }
+    );
+    target.merge(BackendPatch::Memory(5));
+
+    // Soft merges never replace the target.
+    target.merge_soft(BackendPatch::Disabled);
+    assert_eq!(target, Backend::Memory(5));
+
+    // Replacements by variants without fields are only recorded as the variant.
+    let report = target.merge_report(BackendPatch::Disabled);
+    assert_eq!(target, Backend::Disabled);
+    assert_eq!(report.variant, Some("Disabled"));
+    assert!(report.written.is_empty());
+    assert!(report.is_written() && report.is_changed());
+    target.merge(BackendPatch::Memory(5));
+
+    // A src `None` for a required field prevents the replacement.
+    let report = target.merge_report(BackendPatch::S3 {
+        bucket: None,
+        region: "eu".to_string(),
+    });
+    assert_eq!(target, Backend::Memory(5));
+    assert_eq!(report.variant, None);
+    assert_eq!(report.skipped, vec!["bucket", "region"]);
+
+    target.merge(BackendPatch::S3 {
+        bucket: Some("bucket".to_string()),
+        region: "eu".to_string(),
+    });
+    assert_eq!(
+        target,
+        Backend::S3 {
+            bucket: "bucket".to_string(),
+            region: Some("eu".to_string()),
+        }
+    );
+
+    // Raw identifiers are merged just like any other field.
+    target.merge(BackendPatch::Http {
+        r#type: Some("https".to_string()),
+        port: 443,
+    });
+    target.merge_ref(&BackendPatch::Http {
+        r#type: None,
+        port: 8443,
+    });
+    assert_eq!(
+        target,
+        Backend::Http {
+            r#type: "https".to_string(),
+            port: Some(8443),
+        }
+    );
+
+    // Different variants are kept with `#[merge(keep_variant)]`.
+    target.merge(LocalPatch::Local {
+        directory: "/tmp".to_string(),
+    });
+    assert!(matches!(target, Backend::Http { .. }));
+
+    let mut target = Backend::Local {
+        path: "/".to_string(),
+    };
+    target.merge(LocalPatch::Local {
+        directory: "/tmp".to_string(),
+    });
+    assert_eq!(
+        target,
+        Backend::Local {
+            path: "/tmp".to_string()
+        }
+    );
+}
+
+fn merge_option_paths() {
+    let mut target = Qualified {
+        name: Some("target".to_string()),
+        label: None,
+        count: None,
+    };
+
+    target.merge_soft(QualifiedPatch {
+        name: Some("patch".to_string()),
+        label: Some("patch".to_string()),
+        count: 1,
+    });
+    assert_eq!(target.name, Some("target".to_string()));
+    assert_eq!(target.label, Some("patch".to_string()));
+    assert_eq!(target.count, Some(1));
+
+    target.merge(QualifiedPatch {
+        name: None,
+        label: None,
+        count: 2,
+    });
+    assert_eq!(target.name, None);
+    assert_eq!(target.label, None);
+    assert_eq!(target.count, Some(2));
+}
+
+fn merge_aliased() {
+    let mut target = Aliased {
+        nice: Some("target".to_string()),
+        count: 0,
+    };
+
+    // The alias is known to be optional, so soft merges don't overwrite existing values.
+    target.merge_soft(AliasedPatch {
+        nice: Some("patch".to_string()),
+        count: 1,
+    });
+    assert_eq!(target.nice, Some("target".to_string()));
+    assert_eq!(target.count, 0);
+
+    target.merge(AliasedPatch {
+        nice: Some("patch".to_string()),
+        count: 1,
+    });
+    assert_eq!(target.nice, Some("patch".to_string()));
+    assert_eq!(target.count, 1);
+}
+
+fn merge_shapes() {
+    let label = "label".to_string();
+    let mut target = Shapes {
+        name: "target",
+        label: None,
+        position: (0, 0),
+        sizes: [1, 2, 3],
+        colors: [Some(1), None, None],
+    };
+
+    // Soft merges only fill empty elements.
+    let patch = ShapesPatch {
+        name: "patch",
+        label: &label,
+        position: (1, 1),
+        sizes: [None, Some(5), None],
+        colors: [4, 5, 6],
+    };
+    let report = target.merge_ref_soft_report(&patch);
+    assert_eq!(target.name, "target");
+    assert_eq!(target.label, Some(&label));
+    assert_eq!(target.sizes, [1, 2, 3]);
+    assert_eq!(target.colors, [Some(1), Some(5), Some(6)]);
+    assert_eq!(report.written, vec!["label", "colors"]);
+    assert_eq!(report.skipped, vec!["name", "position", "sizes"]);
+
+    let diff = target.merge_preview(&patch);
+    assert_eq!(
+        diff.fields
+            .iter()
+            .map(|field| field.name)
+            .collect::<Vec<_>>(),
+        vec!["name", "position", "sizes", "colors"]
+    );
+    assert_eq!(diff.field("sizes").unwrap().new, "[1, 5, 3]");
+
+    let report = target.merge_report(patch);
+    assert_eq!(target.name, "patch");
+    assert_eq!(target.position, (1, 1));
+    assert_eq!(target.sizes, [1, 5, 3]);
+    assert_eq!(target.colors, [Some(4), Some(5), Some(6)]);
+    assert_eq!(report.changed, vec!["name", "position", "sizes", "colors"]);
+
+    // Conflicts of array elements are reported once per field.
+    let base = ShapesPatch {
+        name: "patch",
+        label: &label,
+        position: (1, 1),
+        sizes: [None, None, None],
+        colors: [4, 5, 6],
+    };
+    let theirs = ShapesPatch {
+        name: "patch",
+        label: &label,
+        position: (1, 1),
+        sizes: [Some(7), Some(8), None],
+        colors: [4, 5, 7],
+    };
+    let conflicts = target.merge3(&base, &theirs);
+    assert_eq!(conflicts, vec!["sizes"]);
+    assert_eq!(target.colors, [Some(4), Some(5), Some(7)]);
+}
+
+fn merge_pointers() {
+    let server = Arc::new(Server {
+        host: "localhost".to_string(),
+        port: None,
+    });
+    let mut target = Deployment {
+        title: Cow::Borrowed("target"),
+        size: 1,
+        limit: Box::new(1),
+        label: None,
+        server: server.clone(),
+        backup: Box::new(Server {
+            host: "backup".to_string(),
+            port: Some(1),
+        }),
+    };
+
+    let patch = DeploymentPatch {
+        title: "patch".to_string(),
+        size: Rc::new(2),
+        limit: Some(2),
+        label: Some("patch".to_string()),
+        server: ServerPatch {
+            host: None,
+            port: 8080,
+        },
+        backup: None,
+    };
+
+    let diff = target.merge_preview(&patch);
+    assert_eq!(
+        diff.fields
+            .iter()
+            .map(|field| field.name)
+            .collect::<Vec<_>>(),
+        vec!["title", "size", "limit", "label", "server"]
+    );
+
+    let report = target.merge_ref_report(&patch);
+    assert_eq!(target.title, "patch");
+    assert_eq!(target.size, 2);
+    assert_eq!(*target.limit, 2);
+    assert_eq!(target.label, Some(Arc::new("patch".to_string())));
+    assert_eq!(report.skipped, vec!["backup"]);
+    assert_eq!(
+        report.changed,
+        vec!["title", "size", "limit", "label", "server"]
+    );
+
+    // The shared server is cloned on write.
+    assert_eq!(target.server.host, "localhost");
+    assert_eq!(target.server.port, Some(8080));
+    assert_eq!(server.port, None);
+
+    target.merge_soft(DeploymentPatch {
+        title: "soft".to_string(),
+        size: Rc::new(3),
+        limit: None,
+        label: Some("soft".to_string()),
+        server: ServerPatch {
+            host: Some("remote".to_string()),
+            port: 80,
+        },
+        backup: Some(ServerPatch {
+            host: Some("remote".to_string()),
+            port: 80,
+        }),
+    });
+    assert_eq!(target.title, "patch");
+    assert_eq!(target.label, Some(Arc::new("patch".to_string())));
+    assert_eq!(target.server.port, Some(8080));
+    assert_eq!(target.backup.host, "backup");
+    assert_eq!(target.backup.port, Some(1));
+
+    target.merge(DeploymentPatch {
+        title: "owned".to_string(),
+        size: Rc::new(4),
+        limit: None,
+        label: None,
+        server: ServerPatch {
+            host: Some("remote".to_string()),
+            port: 80,
+        },
+        backup: Some(ServerPatch {
+            host: Some("remote".to_string()),
+            port: 80,
+        }),
+    });
+    assert_eq!(target.title, "owned");
+    assert_eq!(target.size, 4);
+    assert_eq!(*target.limit, 2);
+    assert_eq!(target.label, None);
+    assert_eq!(target.server.host, "remote");
+    assert_eq!(target.backup.host, "remote");
+    assert_eq!(target.backup.port, Some(80));
+}
+
+fn merge_imports() {
+    let mut target = Inventory {
+        items: vec!["target".to_string()],
+        counts: BTreeMap::new(),
+        server: None,
+    };
+
+    target.merge_soft(InventoryPatch {
+        items: vec!["soft".to_string()],
+        counts: Some(BTreeMap::from([("soft".to_string(), 1)])),
+        server: Server {
+            host: "soft".to_string(),
+            port: None,
+        },
+    });
+    assert_eq!(target.items, vec!["target".to_string()]);
+    assert!(target.counts.is_empty());
+    assert_eq!(target.server.as_ref().unwrap().host, "soft");
+
+    target.merge(InventoryPatch {
+        items: vec!["patch".to_string()],
+        counts: Some(BTreeMap::from([("patch".to_string(), 2)])),
+        server: Server {
+            host: "patch".to_string(),
+            port: Some(80),
+        },
+    });
+    assert_eq!(target.items, vec!["patch".to_string()]);
+    assert_eq!(target.counts.get("patch"), Some(&2));
+    assert_eq!(target.server.unwrap().port, Some(80));
+
+    // `super::` imports resolve to the same type as the target's.
+    let mut profile = Profile {
+        nickname: None,
+        bio: None,
+        tags: None,
+        address: None,
+    };
+    profile.merge(AddressUpdate {
+        address: Address {
+            street: "street".to_string(),
+            city: "city".to_string(),
+        },
+    });
+    assert_eq!(profile.address.unwrap().city, "city");
+}
+
+fn merge_deferred() {
+    let mut target = Metrics {
+        count: 1,
+        source: Box::new("target"),
+    };
+
+    target.merge(MetricsPatch {
+        count: None,
+        source: Box::new("patch"),
+    });
+    assert_eq!(target.count, 1);
+    assert_eq!(target.source.to_string(), "patch");
+
+    target.merge(MetricsPatch {
+        count: Some(2),
+        source: Box::new(3),
+    });
+    assert_eq!(target.count, 2);
+    assert_eq!(target.source.to_string(), "3");
+}
+
+fn merge_patch() {
+    let mut target = Profile {
+        nickname: Some("target".to_string()),
+        bio: Some("target".to_string()),
+        tags: None,
+        address: Some(Address {
+            street: "target".to_string(),
+            city: "target".to_string(),
+        }),
+    };
+
+    // Missing fields are unset, `null` clears the target and values set it.
+    let patch: ProfilePatch =
+        serde_json::from_str(r#"{"bio": null, "tags": ["patch"], "address": {"city": "patch"}}"#)
+            .unwrap();
+    assert_eq!(patch.nickname, Patch::Unset);
+    assert_eq!(patch.bio, Patch::Clear);
+
+    let diff = target.merge_preview(&patch);
+    assert_eq!(
+        diff.fields
+            .iter()
+            .map(|field| field.name)
+            .collect::<Vec<_>>(),
+        vec!["bio", "tags", "address"]
+    );
+
+    let report = target.merge_ref_report(&patch);
+    assert_eq!(target.nickname, Some("target".to_string()));
+    assert_eq!(target.bio, None);
+    assert_eq!(target.tags, Some(vec!["patch".to_string()]));
+    assert_eq!(
+        target.address,
+        Some(Address {
+            street: "target".to_string(),
+            city: "patch".to_string(),
+        })
+    );
+    assert_eq!(report.written, vec!["bio", "tags", "address"]);
+    assert_eq!(report.skipped, vec!["nickname"]);
+    assert_eq!(report.changed, vec!["bio", "tags", "address"]);
+
+    // Soft merges only write into empty fields.
+    target.merge_soft(ProfilePatch {
+        nickname: Patch::Clear,
+        bio: Patch::Set("soft".to_string()),
+        tags: Patch::Set(vec!["soft".to_string()]),
+        address: Patch::Clear,
+    });
+    assert_eq!(target.nickname, Some("target".to_string()));
+    assert_eq!(target.bio, Some("soft".to_string()));
+    assert_eq!(target.tags, Some(vec!["patch".to_string()]));
+    assert!(target.address.is_some());
+
+    target.merge(ProfilePatch {
+        nickname: Patch::Set("owned".to_string()),
+        bio: Patch::Unset,
+        tags: Patch::Clear,
+        address: Patch::Clear,
+    });
+    assert_eq!(target.nickname, Some("owned".to_string()));
+    assert_eq!(target.bio, Some("soft".to_string()));
+    assert_eq!(target.tags, None);
+    assert_eq!(target.address, None);
+
+    // There's no value to merge a nested patch into.
+    target.merge(ProfilePatch {
+        nickname: Patch::Unset,
+        bio: Patch::Unset,
+        tags: Patch::Unset,
+        address: Patch::Set(AddressPatch {
+            street: Some("owned".to_string()),
+            city: None,
+        }),
+    });
+    assert_eq!(target.address, None);
+
+    target.address = Some(Address {
+        street: "target".to_string(),
+        city: "target".to_string(),
+    });
+    let update = ProfileUpdate {
+        nickname: Some(None),
+        address: Some(Some(AddressPatch {
+            street: Some("update".to_string()),
+            city: None,
+        })),
+    };
+    target.merge_ref(&update);
+    assert_eq!(target.nickname, None);
+    assert_eq!(target.address.as_ref().unwrap().street, "update");
+
+    target.merge_ref(&ProfileUpdate {
+        nickname: None,
+        address: Some(None),
+    });
+    assert_eq!(target.address, None);
+
+    let patch: Option<Option<u32>> = Patch::Set(1).into();
+    assert_eq!(patch, Some(Some(1)));
+    assert_eq!(Patch::from(Some(None::<u32>)), Patch::Clear);
+}
+
+fn merge_partial() {
+    let location = Location {
+        city: "target".to_string(),
+        zip: Some(1),
+    };
+    let mut target = Settings {
+        name: "target".to_string(),
+        value: 1,
+        note: Some("target".to_string()),
+        location: location.clone(),
+        backup: None,
+    };
+
+    // Unset fields are left untouched.
+    target.merge(SettingsPatch::default());
+    assert_eq!(target.name, "target");
+    assert_eq!(target.value, 1);
+
+    let patch = SettingsPatch {
+        value: Some(2),
+        note: Patch::Clear,
+        location: Some(serde_json::from_str(r#"{"city": "patch"}"#).unwrap()),
+        backup: Patch::Set(LocationPatch {
+            city: Some("backup".to_string()),
+            zip: Patch::Unset,
+        }),
+        ..Default::default()
+    };
+    let report = target.merge_ref_report(&patch);
+    assert_eq!(target.name, "target");
+    assert_eq!(target.value, 2);
+    assert_eq!(target.note, None);
+    assert_eq!(
+        target.location,
+        Location {
+            city: "patch".to_string(),
+            zip: Some(1),
+        }
+    );
+    // There's no backup to merge into.
+    assert_eq!(target.backup, None);
+    assert_eq!(report.written, vec!["value", "note", "location"]);
+    assert_eq!(report.skipped, vec!["name", "backup"]);
+
+    target.backup = Some(location);
+    target.merge(SettingsPatch {
+        backup: Patch::Set(LocationPatch {
+            city: None,
+            zip: Patch::Clear,
+        }),
+        ..Default::default()
+    });
+    assert_eq!(
+        target.backup,
+        Some(Location {
+            city: "target".to_string(),
+            zip: None,
+        })
+    );
+
+    // Deserialized patches tell missing fields from `null`s, which clear the target.
+    let mut location = Location {
+        city: "target".to_string(),
+        zip: Some(1),
+    };
+    let patch: LocationPatch = serde_json::from_str(r#"{"zip": 2}"#).unwrap();
+    assert_eq!(patch.zip, Patch::Set(2));
+    location.merge(patch);
+    assert_eq!(location.zip, Some(2));
+
+    let patch: LocationPatch = serde_json::from_str(r#"{"city": "patch"}"#).unwrap();
+    assert_eq!(patch.zip, Patch::Unset);
+    location.merge(patch);
+    assert_eq!(location.city, "patch");
+    assert_eq!(location.zip, Some(2));
+
+    let patch: LocationPatch = serde_json::from_str(r#"{"zip": null}"#).unwrap();
+    assert_eq!(patch.zip, Patch::Clear);
+    location.merge(patch);
+    assert_eq!(location.zip, None);
+}
+
+fn extract() {
+    // Optional src fields are wrapped into `Some`.
+    let base = Base::new();
+    let snapshot: Snapshot = base.extract();
+    assert_eq!(snapshot.normal, "base");
+    assert_eq!(snapshot.optional, Some(Some("base".to_string())));
+
+    // Merging the extracted struct doesn't change anything.
+    let mut merged = base.clone();
+    merged.merge_ref(&snapshot);
+    assert_eq!(merged.normal, base.normal);
+    assert_eq!(merged.optional, base.optional);
+
+    // Values are taken out of and wrapped into smart pointers.
+    let deployment = Deployment {
+        title: Cow::Borrowed("deployment"),
+        size: 1,
+        limit: Box::new(2),
+        label: Some(Arc::new("label".to_string())),
+        server: Arc::new(Server {
+            host: "localhost".to_string(),
+            port: Some(80),
+        }),
+        backup: Box::new(Server {
+            host: "backup".to_string(),
+            port: None,
+        }),
+    };
+    let view: DeploymentView = deployment.extract();
+    assert_eq!(view.title, "deployment");
+    assert_eq!(*view.size, 1);
+    assert_eq!(view.limit, 2);
+    assert_eq!(view.label, Some("label".to_string()));
+    assert_eq!(view.server.host, "localhost");
+
+    // Empty targets clear their fields, nested fields are extracted as well.
+    let profile = Profile {
+        nickname: None,
+        bio: Some("bio".to_string()),
+        tags: None,
+        address: Some(Address {
+            street: "street".to_string(),
+            city: "city".to_string(),
+        }),
+    };
+    let patch: ProfilePatch = profile.extract();
+    assert_eq!(patch.nickname, Patch::Clear);
+    assert_eq!(patch.bio, Patch::Set("bio".to_string()));
+    assert_eq!(patch.tags, Patch::Clear);
+    match &patch.address {
+        Patch::Set(address) => {
+            assert_eq!(address.street, Some("street".to_string()));
+            assert_eq!(address.city, Some("city".to_string()));
+        }
+        _ => panic!("The address should be set."),
+    }
+
+    let mut target = Profile {
+        nickname: Some("target".to_string()),
+        bio: None,
+        tags: Some(vec!["target".to_string()]),
+        address: Some(Address {
+            street: "target".to_string(),
+            city: "target".to_string(),
+        }),
+    };
+    target.merge(patch);
+    assert_eq!(target, profile);
+}
+
+fn diff() {
+    // Required fields are always set, optional fields only if they changed.
+    let old = Base::new();
+    let mut new = Base::new();
+    new.normal = "new".to_string();
+    let delta = Delta::diff(&old, &new);
+    assert_eq!(delta.normal, "new");
+    assert_eq!(delta.optional, None);
+
+    new.optional = None;
+    let delta = Delta::diff(&old, &new);
+    assert_eq!(delta.optional, Some(None));
+    assert_eq!(old.merged_ref(&delta).optional, None);
+
+    // Replaying the diff results in the new value.
+    let old = Preferences {
+        name: "old".to_string(),
+        value: 1,
+        note: Some("old".to_string()),
+        location: Location {
+            city: "old".to_string(),
+            zip: Some(1),
+        },
+    };
+    let mut new = old.clone();
+    new.value = 2;
+    new.note = None;
+    new.location.zip = Some(2);
+
+    let patch = PreferencesPatch::diff(&old, &new);
+    assert_eq!(patch.name, None);
+    assert_eq!(patch.value, Some(2));
+    assert_eq!(patch.note, Patch::Clear);
+    // Nested fields only contain their own changes.
+    let location = patch.location.as_ref().unwrap();
+    assert_eq!(location.city, None);
+    assert_eq!(location.zip, Patch::Set(2));
+    assert_eq!(old.merged(patch), new);
+
+    // Unchanged structs result in an empty patch.
+    let patch = PreferencesPatch::diff(&old, &old);
+    assert!(patch.location.is_none() && patch.note.is_unset());
+
+    // Tri-state patches are unset, cleared or set.
+    let old = Profile {
+        nickname: Some("old".to_string()),
+        bio: Some("old".to_string()),
+        tags: None,
+        address: None,
+    };
+    let mut new = old.clone();
+    new.bio = None;
+    new.tags = Some(vec!["new".to_string()]);
+    let address = Address {
+        street: "new".to_string(),
+        city: "new".to_string(),
+    };
+    new.address = Some(address.clone());
+
+    let patch = ProfileDiff::diff(&old, &new);
+    assert_eq!(patch.nickname, Patch::Unset);
+    assert_eq!(patch.bio, Patch::Clear);
+    assert_eq!(patch.tags, Patch::Set(vec!["new".to_string()]));
+    // Values that only exist in the new target are set as a whole.
+    assert_eq!(patch.address, Patch::Set(address));
+    assert_eq!(old.merged_ref(&patch), new);
+
+    let patch = ProfileDiff::diff(&new, &old);
+    assert_eq!(patch.address, Patch::Clear);
+    assert_eq!(new.merged_ref(&patch), old);
+}
+
+fn merge_bidirectional() {
+    let mut record = ContactRecord {
+        id: 1,
+        name: "record".to_string(),
+        mail: None,
+        phone: "123".to_string(),
+    };
+    let mut contact = Contact {
+        name: "contact".to_string(),
+        mail: Some("contact@example.com".to_string()),
+        number: None,
+    };
+
+    // Renamed fields are mapped in both directions.
+    contact.merge_ref(&record);
+    assert_eq!(contact.name, "record");
+    assert_eq!(contact.mail, None);
+    assert_eq!(contact.number, Some("123".to_string()));
+
+    contact.name = "contact".to_string();
+    contact.number = Some("456".to_string());
+    record.merge(contact.clone());
+    assert_eq!(record.id, 1);
+    assert_eq!(record.name, "contact");
+    assert_eq!(record.phone, "456");
+
+    // Soft merges only write into empty fields in both directions.
+    contact.mail = None;
+    contact.merge_soft(ContactRecord {
+        id: 2,
+        name: "soft".to_string(),
+        mail: Some("soft@example.com".to_string()),
+        phone: "789".to_string(),
+    });
+    assert_eq!(contact.name, "contact");
+    assert_eq!(contact.mail, Some("soft@example.com".to_string()));
+    assert_eq!(contact.number, Some("456".to_string()));
+
+    // The target may be located in another module than the src.
+    let mut summary = ContactSummary {
+        name: "summary".to_string(),
+        mail: None,
+    };
+    summary.merge(record.clone());
+    assert_eq!(
+        summary,
+        ContactSummary {
+            name: "contact".to_string(),
+            mail: None,
+        }
+    );
+    summary.mail = Some("summary@example.com".to_string());
+    record.merge(summary);
+    assert_eq!(record.name, "contact");
+    assert_eq!(record.mail, Some("summary@example.com".to_string()));
+}
+
+fn merge_modes() {
+    let mut endpoint = Endpoint {
+        url: "endpoint".to_string(),
+        timeout: None,
+    };
+    let patch = EndpointPatch {
+        url: "patch".to_string(),
+        timeout: 10,
+    };
+
+    // The borrowed merges leave the patch untouched.
+    let report = endpoint.merge_ref_report(&patch);
+    assert_eq!(endpoint.url, "patch");
+    assert_eq!(endpoint.timeout, Some(10));
+    assert_eq!(report.written, vec!["url", "timeout"]);
+
+    endpoint.url = "endpoint".to_string();
+    endpoint.merge(EndpointPatch {
+        timeout: 20,
+        ..patch
+    });
+    assert_eq!(endpoint.url, "patch");
+    assert_eq!(endpoint.timeout, Some(20));
+}
+
+fn merge_target_options() {
+    let record = Record {
+        id: 1,
+        name: "record".to_string(),
+        body: "record".to_string(),
+    };
+
+    // `id` is skipped and `name` is merged into `title`.
+    let mut article = Article {
+        id: "article".to_string(),
+        title: "article".to_string(),
+        body: None,
+    };
+    let report = article.merge_report(record.clone());
+    assert_eq!(
+        article,
+        Article {
+            id: "article".to_string(),
+            title: "record".to_string(),
+            body: Some("record".to_string()),
+        }
+    );
+    assert_eq!(report.written, vec!["title", "body"]);
+
+    let mut entry = Entry {
+        id: 0,
+        name: "entry".to_string(),
+        body: "entry".to_string(),
+    };
+    entry.merge(record);
+    assert_eq!(entry.id, 1);
+    assert_eq!(entry.name, "record");
+}
+
+fn merge_references() {
+    let tags = vec!["rust".to_string()];
+    let body = "three short words".to_string();
+    let document_ref = DocumentRef {
+        title: "title",
+        tags: &tags,
+        revision: 2,
+        summary: None,
+        body: Some(&body),
+    };
+
+    let mut document = Document {
+        title: "old".to_string(),
+        tags: Vec::new(),
+        revision: 1,
+        summary: Some("old".to_string()),
+        words: 0,
+    };
+    let preview = document.merge_preview(&document_ref);
+    assert_eq!(
+        preview
+            .fields
+            .iter()
+            .map(|field| field.name)
+            .collect::<Vec<_>>(),
+        vec!["title", "tags", "revision", "summary", "words"]
+    );
+
+    // The values are converted from the references of the src.
+    document.merge_ref(&document_ref);
+    assert_eq!(
+        document,
+        Document {
+            title: "title".to_string(),
+            tags: vec!["rust".to_string()],
+            revision: 2,
+            summary: None,
+            words: 3,
+        }
+    );
+    assert!(document.merge_preview(&document_ref).fields.is_empty());
+
+    // Owned merges convert the values just the same.
+    let document_ref = DocumentRef {
+        title: "owned",
+        tags: &[],
+        revision: 3,
+        summary: Some("summary"),
+        body: None,
+    };
+    document.merge_soft(document_ref);
+    assert_eq!(document.title, "title");
+    assert_eq!(document.summary, Some("summary".to_string()));
+    document.merge(DocumentRef {
+        title: "owned",
+        tags: &[],
+        revision: 3,
+        summary: Some("summary"),
+        body: None,
+    });
+    assert_eq!(
+        document,
+        Document {
+            title: "owned".to_string(),
+            tags: Vec::new(),
+            revision: 3,
+            summary: Some("summary".to_string()),
+            words: 3,
+        }
+    );
+}
+
+fn merge_take() {
+    let mut buffer = Buffer::default();
+    let mut patch = BufferPatch {
+        name: "buffer".to_string(),
+        lines: Some(vec!["line".to_string()]),
+        comment: "note".to_string(),
+        title: Some("title".to_string()),
+        limit: 10,
+    };
+
+    // The values are moved into the target, the patch can be reused afterwards.
+    let report = buffer.merge_take_report(&mut patch);
+    assert_eq!(
+        buffer,
+        Buffer {
+            name: "buffer".to_string(),
+            lines: vec!["line".to_string()],
+            note: Some("note".to_string()),
+            title: Some("title".to_string()),
+            limit: 10,
+        }
+    );
+    assert_eq!(
+        patch,
+        BufferPatch {
+            limit: 10,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        report.written,
+        vec!["name", "lines", "note", "title", "limit"]
+    );
+    assert_eq!(
+        report.changed,
+        vec!["name", "lines", "note", "title", "limit"]
+    );
+
+    // Values that aren't merged by soft merges are left in the patch.
+    patch.name = "soft".to_string();
+    patch.lines = Some(vec!["soft".to_string()]);
+    patch.comment = "soft".to_string();
+    buffer.title = None;
+    patch.title = Some("soft".to_string());
+    buffer.merge_take_soft(&mut patch);
+    assert_eq!(buffer.name, "buffer");
+    assert_eq!(buffer.note, Some("note".to_string()));
+    assert_eq!(buffer.title, Some("soft".to_string()));
+    assert_eq!(patch.name, "soft");
+    assert_eq!(patch.lines, Some(vec!["soft".to_string()]));
+    assert_eq!(patch.comment, "soft");
+    assert_eq!(patch.title, None);
+
+    // Missing optional values are skipped.
+    patch.lines = None;
+    buffer.merge_take(&mut patch);
+    assert_eq!(buffer.name, "soft");
+    assert_eq!(buffer.lines, vec!["line".to_string()]);
+    assert_eq!(buffer.note, Some("soft".to_string()));
+
+    // The renamed field is merged by value as well.
+    buffer.merge(BufferPatch {
+        comment: "owned".to_string(),
+        ..Default::default()
+    });
+    assert_eq!(buffer.note, Some("owned".to_string()));
+}
+
+/// Test [StructMerge::merge_all], [merge_chain] and [FoldMerge::fold_merge].
+fn merge_layers() {
+    let layer = |name: &str, optional: Option<Option<&str>>| Mixed {
+        normal: name.to_string(),
+        optional: optional.map(|optional| optional.map(str::to_string)),
+    };
+
+    // Later layers take precedence, missing values are skipped.
+    let mut base = Base::new();
+    base.merge_all([
+        layer("defaults", Some(Some("defaults"))),
+        layer("file", None),
+        layer("cli", None),
+    ]);
+    assert_eq!(base.normal, "cli");
+    assert_eq!(base.optional, Some("defaults".to_string()));
+    assert_eq!(base.ignored, "base");
+
+    // Layers of different types are merged in the given order.
+    let mut base = Base::new();
+    merge_chain!(
+        base,
+        layer("defaults", Some(None)),
+        Layer::new(),
+        Layer {
+            normal: None,
+            optional: None,
+        },
+    );
+    assert_eq!(base.normal, "layer");
+    assert_eq!(base.optional, Some("layer".to_string()));
+
+    // Targets are commonly borrowed, e.g. as function parameters.
+    fn apply(base: &mut Base, cli: Mixed) {
+        merge_chain!(base, Layer::new(), cli);
+    }
+    let mut base = Base::new();
+    apply(&mut base, layer("cli", None));
+    assert_eq!(base.normal, "cli");
+    assert_eq!(base.optional, Some("layer".to_string()));
+
+    let base = vec![layer("env", None), layer("cli", Some(None))]
+        .into_iter()
+        .fold_merge(Base::new());
+    assert_eq!(base.normal, "cli");
+    assert_eq!(base.optional, None);
+}
+
+/// Test [Tracked::merge_tracked] and the [Provenance](struct_merge::Provenance) of its fields.
+fn merge_tracked() {
+    let mut base = Tracked::new(Base::new());
+    assert_eq!(base.provenance().field("normal"), None);
+
+    base.merge_tracked(Mixed::new(), "file");
+    base.merge_tracked(
+        Layer {
+            normal: Some("env".to_string()),
+            optional: None,
+        },
+        "env",
+    );
+    base.merge_ref_tracked(
+        &Optional {
+            normal: None,
+            optional: Some(None),
+        },
+        "cli",
+    );
+
+    let provenance = base.provenance();
+    assert_eq!(provenance.field("normal"), Some("env"));
+    assert_eq!(provenance.field("optional"), Some("cli"));
+    assert_eq!(provenance.field("ignored"), None);
+    assert_eq!(
+        provenance.fields().collect::<Vec<_>>(),
+        vec![("normal", "env"), ("optional", "cli")]
+    );
+
+    // Hand-written merges report their fields themselves.
+    let mut renamed = Tracked::new(Base::new());
+    let report = renamed.merge_tracked(
+        Renamed {
+            name: "renamed".to_string(),
+        },
+        "manual",
+    );
+    assert_eq!(renamed.normal, "renamed");
+    assert_eq!(report.written, vec!["normal"]);
+    assert_eq!(renamed.provenance().field("normal"), Some("manual"));
+
+    // The target itself is merged as usual.
+    assert_eq!(base.normal, "env");
+    let (base, _) = base.into_parts();
+    assert_eq!(base.optional, None);
+}
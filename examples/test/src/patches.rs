@@ -0,0 +1,78 @@
+use std::collections::BTreeMap as Map;
+
+use serde::Deserialize;
+use struct_merge::{struct_merge, struct_merge_ref, Patch};
+
+use super::structs::Address;
+use crate::structs;
+
+/// A struct, whose types are imported under other names than the ones of its target.
+#[struct_merge(crate::structs::Inventory)]
+pub struct InventoryPatch {
+    pub items: std::vec::Vec<String>,
+    pub counts: Option<Map<String, u32>>,
+    pub server: structs::Server,
+}
+
+/// A struct, whose field type is imported relative to this module.
+#[struct_merge(crate::structs::Profile)]
+pub struct AddressUpdate {
+    pub address: Address,
+}
+
+/// A REST-style patch, whose fields are either missing, `null` or set.
+#[struct_merge(crate::structs::Profile)]
+#[struct_merge_ref(crate::structs::Profile)]
+#[merge(track_changes, preview, extract)]
+#[derive(Deserialize)]
+pub struct ProfilePatch {
+    #[serde(default)]
+    pub nickname: Patch<String>,
+    #[serde(default)]
+    pub bio: Patch<String>,
+    #[serde(default)]
+    pub tags: Patch<Vec<String>>,
+    #[serde(default)]
+    #[merge(nested)]
+    pub address: Patch<AddressPatch>,
+}
+
+/// A patch of the nested [structs::Address].
+#[struct_merge(crate::structs::Address)]
+#[struct_merge_ref(crate::structs::Address)]
+#[merge(track_changes, extract, diff)]
+#[derive(Deserialize)]
+pub struct AddressPatch {
+    #[serde(default)]
+    pub street: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+}
+
+/// A tri-state patch, which is built from the changes between two [structs::Profile]s.
+/// The address is diffed as a whole, as a nested merge couldn't create a missing address.
+#[struct_merge_ref(crate::structs::Profile)]
+#[merge(diff)]
+pub struct ProfileDiff {
+    pub nickname: Patch<String>,
+    pub bio: Patch<String>,
+    pub tags: Patch<Vec<String>>,
+    pub address: Patch<Address>,
+}
+
+/// The `Option<Option<T>>` counterpart of a tri-state patch.
+#[struct_merge_ref(crate::structs::Profile)]
+pub struct ProfileUpdate {
+    pub nickname: Option<Option<String>>,
+    #[merge(nested)]
+    pub address: Option<Option<AddressPatch>>,
+}
+
+/// A struct in another module than its target, which is merged into [structs::ContactRecord]
+/// and vice versa.
+#[struct_merge(crate::structs::ContactRecord, bidirectional)]
+#[derive(Debug, PartialEq)]
+pub struct ContactSummary {
+    pub name: String,
+    pub mail: Option<String>,
+}
@@ -1,5 +1,15 @@
-use struct_merge::{struct_merge, struct_merge_ref};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
 
+use serde::Deserialize;
+use struct_merge::{
+    struct_merge, struct_merge3, struct_merge_partial, struct_merge_ref, struct_merge_take,
+    try_struct_merge, MergeError, MergeReport, StructMergeInto,
+};
+
+#[derive(Clone)]
 pub struct Base {
     pub normal: String,
     pub optional: Option<String>,
@@ -67,3 +77,599 @@ impl Mixed {
         }
     }
 }
+
+/// A struct with identical field types, whose merge reports record the changed fields.
+#[struct_merge_ref(crate::structs::Base)]
+#[merge(track_changes)]
+pub struct Tracking {
+    pub normal: String,
+    pub optional: Option<String>,
+}
+
+impl Tracking {
+    pub fn new() -> Self {
+        Tracking {
+            normal: "tracking".to_string(),
+            optional: Some("tracking".to_string()),
+        }
+    }
+}
+
+/// A struct with identical field types, whose merges can be previewed.
+#[struct_merge_ref(crate::structs::Base)]
+#[merge(preview)]
+pub struct Preview {
+    pub normal: String,
+    pub optional: Option<String>,
+}
+
+impl Preview {
+    pub fn new() -> Self {
+        Preview {
+            normal: "preview".to_string(),
+            optional: Some("preview".to_string()),
+        }
+    }
+}
+
+/// A struct with both, identical and optional field types, which is extracted from [Base].
+#[struct_merge_ref(crate::structs::Base)]
+#[merge(extract)]
+pub struct Snapshot {
+    pub normal: String,
+    pub optional: Option<Option<String>>,
+}
+
+/// A struct with both, identical and optional field types, which is built from the changes
+/// between two [Base]s.
+#[struct_merge_ref(crate::structs::Base)]
+#[merge(diff)]
+pub struct Delta {
+    pub normal: String,
+    pub optional: Option<Option<String>>,
+}
+
+/// A layer of configuration with optional fields, which is merged into [Base] by value.
+#[struct_merge(crate::structs::Base)]
+pub struct Layer {
+    pub normal: Option<String>,
+    pub optional: Option<Option<String>>,
+}
+
+impl Layer {
+    pub fn new() -> Self {
+        Layer {
+            normal: Some("layer".to_string()),
+            optional: Some(Some("layer".to_string())),
+        }
+    }
+}
+
+/// A revision of [Base], which is merged via three-way merges.
+#[struct_merge3(crate::structs::Base)]
+pub struct Revision {
+    pub normal: String,
+    pub optional: Option<String>,
+}
+
+impl Revision {
+    pub fn new() -> Self {
+        Revision {
+            normal: "revision".to_string(),
+            optional: Some("revision".to_string()),
+        }
+    }
+}
+
+/// A revision of [Base] with optional fields, which is merged via three-way merges.
+#[struct_merge3(crate::structs::Base)]
+pub struct OptionalRevision {
+    pub normal: String,
+    pub optional: Option<Option<String>>,
+}
+
+/// The target for fallible merges.
+#[derive(Debug, Default, PartialEq)]
+pub struct Limits {
+    pub min: u8,
+    pub max: u8,
+    pub label: Option<String>,
+    pub r#type: u8,
+}
+
+/// Make sure the limits are in a valid state.
+fn validate_limits(limits: &Limits) -> Result<(), String> {
+    if limits.min > limits.max {
+        return Err(format!("{} is greater than {}", limits.min, limits.max));
+    }
+
+    Ok(())
+}
+
+/// Labels must not be empty.
+fn validate_label(label: &str) -> Result<(), &'static str> {
+    if label.is_empty() {
+        return Err("label is empty");
+    }
+
+    Ok(())
+}
+
+/// A struct, whose fields have to be converted to be merged into [Limits].
+#[try_struct_merge(crate::structs::Limits)]
+#[merge(validate = "validate_limits")]
+pub struct LimitsPatch {
+    pub min: Option<u32>,
+    pub max: u32,
+    #[merge(validate = "validate_label")]
+    pub label: String,
+}
+
+/// A struct with a raw identifier, which is converted during fallible merges.
+#[try_struct_merge(crate::structs::Limits)]
+pub struct TypePatch {
+    pub r#type: u16,
+}
+
+/// A custom error for fallible merges.
+#[derive(Debug)]
+pub struct LimitsError(pub MergeError);
+
+impl From<MergeError> for LimitsError {
+    fn from(error: MergeError) -> Self {
+        LimitsError(error)
+    }
+}
+
+/// A struct that uses a custom error type for fallible merges.
+#[try_struct_merge(crate::structs::Limits)]
+#[merge(error = "LimitsError")]
+pub struct MaxPatch {
+    pub max: u16,
+}
+
+/// A generic target struct with a lifetime.
+pub struct GenericTarget<'a, T>
+where
+    T: Clone,
+{
+    pub value: T,
+    pub optional: Option<T>,
+    pub label: Option<&'a str>,
+}
+
+/// A generic src struct, which merges into [GenericTarget] for all `T`.
+/// The generic parameters of both structs have the same names and are thereby unified.
+#[struct_merge(crate::structs::GenericTarget)]
+#[struct_merge_ref(crate::structs::GenericTarget)]
+pub struct GenericPatch<'a, T: Clone> {
+    pub value: Option<T>,
+    pub optional: T,
+    pub label: Option<&'a str>,
+}
+
+/// A struct that only merges into a [GenericTarget] with a specific type.
+#[struct_merge(crate::structs::GenericTarget<'static, String>)]
+pub struct StringPatch {
+    pub value: String,
+    pub label: Option<&'static str>,
+}
+
+/// A tuple struct, which is used as a merge target.
+#[derive(Clone)]
+pub struct Point(pub i32, pub i32, pub Option<String>);
+
+/// A tuple struct, whose fields are merged into [Point] by their position.
+#[struct_merge(crate::structs::Point)]
+#[struct_merge_ref(crate::structs::Point)]
+pub struct PointPatch(pub Option<i32>, pub i32, pub String);
+
+/// A struct with named fields, which are mapped onto the fields of [Point].
+#[struct_merge(crate::structs::Point)]
+pub struct NamedPoint {
+    #[merge(index = 0)]
+    pub x: i32,
+    #[merge(index = 1)]
+    pub y: Option<i32>,
+}
+
+/// A tuple struct, whose fields are mapped onto the fields of [Base].
+#[struct_merge_ref(crate::structs::Base)]
+pub struct BaseTuple(
+    #[merge(name = "normal")] pub String,
+    #[merge(name = "optional")] pub Option<String>,
+);
+
+/// An enum, which is used as a merge target.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backend {
+    S3 {
+        bucket: String,
+        region: Option<String>,
+    },
+    Local {
+        path: String,
+    },
+    Memory(usize),
+    Http {
+        r#type: String,
+        port: Option<u16>,
+    },
+    Disabled,
+}
+
+/// An enum, whose variants are merged into the respective variants of [Backend].
+#[struct_merge(crate::structs::Backend)]
+#[struct_merge_ref(crate::structs::Backend)]
+#[merge(track_changes)]
+pub enum BackendPatch {
+    S3 {
+        bucket: Option<String>,
+        region: String,
+    },
+    Local {
+        path: String,
+    },
+    Memory(usize),
+    Http {
+        r#type: Option<String>,
+        port: u16,
+    },
+    Disabled,
+}
+
+/// An enum, which only merges into [Backend] if the variants are the same.
+#[struct_merge(crate::structs::Backend)]
+#[merge(keep_variant)]
+pub enum LocalPatch {
+    Local {
+        #[merge(name = "path")]
+        directory: String,
+    },
+}
+
+/// An alias of `Option`, which hides the inner type.
+pub type MaybeString = Option<String>;
+
+/// A generic alias of `Option`.
+pub type Maybe<T> = Option<T>;
+
+/// A target struct with qualified and aliased `Option` types.
+pub struct Qualified {
+    pub name: std::option::Option<String>,
+    pub label: MaybeString,
+    pub count: Maybe<u32>,
+}
+
+/// A struct, which uses qualified `Option` paths and aliases.
+#[struct_merge(crate::structs::Qualified)]
+#[merge(option_aliases(MaybeString, Maybe))]
+pub struct QualifiedPatch {
+    pub name: ::core::option::Option<String>,
+    pub label: MaybeString,
+    pub count: u32,
+}
+
+/// An alias of `Option`, which isn't declared via `#[merge(option_aliases(...))]`.
+pub type Nice = Option<String>;
+
+/// A target struct, whose optional field is hidden behind an alias.
+pub struct Aliased {
+    pub nice: Nice,
+    pub count: u32,
+}
+
+/// A struct, whose fields are explicitly marked as optional and required.
+/// Unannotated fields, that might be aliases, result in an error.
+#[struct_merge(crate::structs::Aliased)]
+#[merge(deny_aliases)]
+pub struct AliasedPatch {
+    #[merge(optional, target_optional)]
+    pub nice: Nice,
+    pub count: u32,
+}
+
+/// A target struct with references, tuples and arrays.
+#[derive(Clone)]
+pub struct Shapes<'a> {
+    pub name: &'a str,
+    pub label: Option<&'a String>,
+    pub position: (i32, i32),
+    pub sizes: [u32; 3],
+    pub colors: [Option<u8>; 3],
+}
+
+/// A struct, whose arrays are merged element-wise into [Shapes].
+#[struct_merge(crate::structs::Shapes)]
+#[struct_merge_ref(crate::structs::Shapes)]
+#[struct_merge3(crate::structs::Shapes)]
+#[merge(track_changes, preview)]
+pub struct ShapesPatch<'a> {
+    pub name: &'a str,
+    pub label: &'a String,
+    pub position: (i32, i32),
+    pub sizes: [Option<u32>; 3],
+    pub colors: [u8; 3],
+}
+
+/// A target struct, which is nested in [Deployment].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Server {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// A struct, which is merged into a nested [Server].
+#[struct_merge(crate::structs::Server)]
+#[struct_merge_ref(crate::structs::Server)]
+#[merge(track_changes)]
+pub struct ServerPatch {
+    pub host: Option<String>,
+    pub port: u16,
+}
+
+/// A target struct with smart pointers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deployment<'a> {
+    pub title: Cow<'a, str>,
+    pub size: u32,
+    pub limit: Box<u64>,
+    pub label: Option<Arc<String>>,
+    pub server: Arc<Server>,
+    pub backup: Box<Server>,
+}
+
+/// A struct, whose values are wrapped into and unwrapped from smart pointers.
+#[struct_merge(crate::structs::Deployment)]
+#[struct_merge_ref(crate::structs::Deployment)]
+#[merge(track_changes, preview)]
+pub struct DeploymentPatch {
+    pub title: String,
+    pub size: Rc<u32>,
+    pub limit: Option<u64>,
+    pub label: Option<String>,
+    #[merge(nested)]
+    pub server: ServerPatch,
+    #[merge(nested)]
+    pub backup: Option<ServerPatch>,
+}
+
+/// A snapshot of a [Deployment], whose values are taken out of their smart pointers.
+#[struct_merge_ref(crate::structs::Deployment)]
+#[merge(extract)]
+pub struct DeploymentView {
+    pub title: String,
+    pub size: Rc<u32>,
+    pub limit: u64,
+    pub label: Option<String>,
+    pub server: Server,
+}
+
+/// A target struct, whose types are written differently by its src struct
+/// [crate::patches::InventoryPatch].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inventory {
+    pub items: Vec<String>,
+    pub counts: BTreeMap<String, u32>,
+    pub server: Option<Server>,
+}
+
+/// An alias, which the macro cannot resolve.
+pub type Count = u32;
+
+/// A target struct, whose types are only known to be the same by the compiler.
+pub struct Metrics {
+    pub count: Count,
+    pub source: Box<dyn std::fmt::Display + Send>,
+}
+
+/// A struct, whose field types are checked by the compiler instead of the macro.
+#[struct_merge(crate::structs::Metrics)]
+#[merge(defer_type_checks)]
+pub struct MetricsPatch {
+    pub count: Option<u32>,
+    pub source: Box<dyn Send + std::fmt::Display>,
+}
+
+/// A target struct, whose fields can be cleared via tri-state patches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub nickname: Option<String>,
+    pub bio: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub address: Option<Address>,
+}
+
+/// A nested target struct of [Profile].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Address {
+    pub street: String,
+    pub city: String,
+}
+
+/// A target struct, whose partial struct is generated.
+#[struct_merge_partial(
+    name = "LocationPatch",
+    derive(Debug, Default, Deserialize),
+    attr(serde(default))
+)]
+#[merge(diff)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub city: String,
+    pub zip: Option<u32>,
+}
+
+/// A generic target struct, whose partial struct contains the partial struct of [Location].
+#[struct_merge_partial(name = "SettingsPatch", derive(Default))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings<T> {
+    /// The name of the settings.
+    pub name: String,
+    pub value: T,
+    pub note: Option<String>,
+    #[merge(partial = "LocationPatch")]
+    pub location: Location,
+    #[merge(partial = "LocationPatch")]
+    pub backup: Option<Location>,
+}
+
+/// A generic target struct, whose partial struct is built from the changes between two targets.
+#[struct_merge_partial(name = "PreferencesPatch", derive(Default))]
+#[merge(diff)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preferences<T> {
+    pub name: String,
+    pub value: T,
+    pub note: Option<String>,
+    #[merge(partial = "LocationPatch")]
+    pub location: Location,
+}
+
+/// A target struct, which is merged back into [Contact].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactRecord {
+    pub id: u32,
+    pub name: String,
+    pub mail: Option<String>,
+    pub phone: String,
+}
+
+/// A struct, which is merged into [ContactRecord] and vice versa.
+#[struct_merge(crate::structs::ContactRecord, bidirectional)]
+#[struct_merge_ref(crate::structs::ContactRecord, bidirectional)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    pub name: String,
+    pub mail: Option<String>,
+    #[merge(name = "phone")]
+    pub number: Option<String>,
+}
+
+/// A target struct of [EndpointPatch].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    pub url: String,
+    pub timeout: Option<u32>,
+}
+
+/// A struct, whose owned and borrowed merges are generated at once.
+#[struct_merge(crate::structs::Endpoint, mode = "both")]
+#[derive(Clone)]
+pub struct EndpointPatch {
+    pub url: String,
+    pub timeout: u32,
+}
+
+/// A target struct, whose `id` is of another type than the one of [Record].
+#[derive(Debug, PartialEq)]
+pub struct Article {
+    pub id: String,
+    pub title: String,
+    pub body: Option<String>,
+}
+
+/// A target struct with all fields of [Record].
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub id: u32,
+    pub name: String,
+    pub body: String,
+}
+
+/// A struct, which is merged into several targets with target-specific rules.
+#[struct_merge([
+    crate::structs::Article(skip(id), rename(name = title)),
+    crate::structs::Entry(strict),
+])]
+#[derive(Clone)]
+pub struct Record {
+    pub id: u32,
+    pub name: String,
+    pub body: String,
+}
+
+/// A target struct, which is merged from the borrowed values of [DocumentRef].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub revision: u32,
+    pub summary: Option<String>,
+    pub words: usize,
+}
+
+/// A struct, which holds references to the values that are merged into [Document].
+#[struct_merge(crate::structs::Document, mode = "both")]
+#[merge(preview)]
+pub struct DocumentRef<'a> {
+    #[merge(to_owned)]
+    pub title: &'a str,
+    #[merge(to_owned)]
+    pub tags: &'a [String],
+    #[merge(copy)]
+    pub revision: u32,
+    #[merge(to_owned)]
+    pub summary: Option<&'a str>,
+    #[merge(name = "words", ref_with = "count_words")]
+    pub body: Option<&'a str>,
+}
+
+/// Count the words of a [DocumentRef]'s body.
+fn count_words(body: &&str) -> usize {
+    body.split_whitespace().count()
+}
+
+/// A target struct, which takes the values of a reused [BufferPatch].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Buffer {
+    pub name: String,
+    pub lines: Vec<String>,
+    pub note: Option<String>,
+    pub title: Option<String>,
+    pub limit: usize,
+}
+
+/// A struct, whose values are moved into [Buffer], leaving defaults behind.
+/// It's merged by value as well, so the field options must survive the other macro.
+#[struct_merge(crate::structs::Buffer)]
+#[struct_merge_take(crate::structs::Buffer)]
+#[merge(track_changes)]
+#[derive(Debug, Default, PartialEq)]
+pub struct BufferPatch {
+    pub name: String,
+    pub lines: Option<Vec<String>>,
+    #[merge(name = "note")]
+    pub comment: String,
+    pub title: Option<String>,
+    #[merge(copy)]
+    pub limit: usize,
+}
+
+/// A struct with a hand-written merge, which reports its fields itself.
+pub struct Renamed {
+    pub name: String,
+}
+
+impl StructMergeInto<Base> for Renamed {
+    fn merge_into(self, target: &mut Base) {
+        target.normal = self.name;
+    }
+
+    fn merge_into_soft(self, _target: &mut Base) {}
+
+    fn merge_into_report(self, target: &mut Base) -> MergeReport {
+        self.merge_into(target);
+        MergeReport {
+            written: vec!["normal"],
+            ..Default::default()
+        }
+    }
+
+    fn merge_into_soft_report(self, _target: &mut Base) -> MergeReport {
+        MergeReport {
+            skipped: vec!["normal"],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,6 @@
+/// Check the error messages of invalid macro invocations.
+#[test]
+fn compile_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
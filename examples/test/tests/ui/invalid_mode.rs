@@ -0,0 +1,12 @@
+use struct_merge::struct_merge;
+
+pub struct Target {
+    pub name: String,
+}
+
+#[struct_merge(crate::Target, mode = "shared")]
+pub struct Patch {
+    pub name: String,
+}
+
+fn main() {}
@@ -1,3 +1,8 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
 pub use struct_merge_codegen::*;
 
 /// Merge another struct into `Self`.
@@ -23,6 +28,78 @@ pub trait StructMerge<Src> {
     /// assert_eq!(target.a, "test".to_string());
     /// ```
     fn merge_soft(&mut self, src: Src);
+
+    /// Same as [StructMerge::merge], but a [MergeReport] of all touched fields is returned.
+    fn merge_report(&mut self, src: Src) -> MergeReport;
+
+    /// Same as [StructMerge::merge_soft], but a [MergeReport] of all touched fields is returned.
+    fn merge_soft_report(&mut self, src: Src) -> MergeReport;
+
+    /// Merge the given struct into a clone of `Self` and return it.
+    /// `Self` is left untouched.
+    ///
+    /// For example:
+    /// ```ignore
+    /// let config = defaults.merged(patch);
+    /// ```
+    fn merged(&self, src: Src) -> Self
+    where
+        Self: Clone,
+    {
+        let mut target = self.clone();
+        target.merge(src);
+        target
+    }
+
+    /// Same as [StructMerge::merged], but the given struct is merged via
+    /// [StructMerge::merge_soft].
+    fn merged_soft(&self, src: Src) -> Self
+    where
+        Self: Clone,
+    {
+        let mut target = self.clone();
+        target.merge_soft(src);
+        target
+    }
+
+    /// Merge the given struct into `Self` whilst consuming both and return the result.
+    ///
+    /// Contrary to [StructMerge::merged], `Self` doesn't need to implement [Clone].
+    fn into_merged(mut self, src: Src) -> Self
+    where
+        Self: Sized,
+    {
+        self.merge(src);
+        self
+    }
+
+    /// Same as [StructMerge::into_merged], but the given struct is merged via
+    /// [StructMerge::merge_soft].
+    fn into_merged_soft(mut self, src: Src) -> Self
+    where
+        Self: Sized,
+    {
+        self.merge_soft(src);
+        self
+    }
+
+    /// Merge all given structs into `Self` in the given order whilst consuming them.
+    ///
+    /// Later structs take precedence over earlier ones, e.g. for layered configuration.
+    /// Structs of different types can be merged via [merge_chain].
+    ///
+    /// For example:
+    /// ```ignore
+    /// config.merge_all([file, env, cli]);
+    /// ```
+    fn merge_all<I>(&mut self, srcs: I)
+    where
+        I: IntoIterator<Item = Src>,
+    {
+        for src in srcs {
+            self.merge(src);
+        }
+    }
 }
 
 /// Counterpart of [StructMerge].
@@ -33,6 +110,12 @@ pub trait StructMergeInto<Target: ?Sized> {
 
     /// Check the [StructMerge::merge_soft] docs.
     fn merge_into_soft(self, target: &mut Target);
+
+    /// Check the [StructMerge::merge_report] docs.
+    fn merge_into_report(self, target: &mut Target) -> MergeReport;
+
+    /// Check the [StructMerge::merge_soft_report] docs.
+    fn merge_into_soft_report(self, target: &mut Target) -> MergeReport;
 }
 
 /// Implement the [StructMerge] trait for all types that provide [StructMergeInto] for it.
@@ -44,11 +127,59 @@ impl<Target, Src: StructMergeInto<Target>> StructMerge<Src> for Target {
     fn merge_soft(&mut self, src: Src) {
         src.merge_into_soft(self);
     }
+
+    fn merge_report(&mut self, src: Src) -> MergeReport {
+        src.merge_into_report(self)
+    }
+
+    fn merge_soft_report(&mut self, src: Src) -> MergeReport {
+        src.merge_into_soft_report(self)
+    }
+}
+
+/// Merge structs of different types into a target in the given order whilst consuming them.
+///
+/// Later structs take precedence over earlier ones. Each struct is merged via
+/// [StructMerge::merge], so the target must implement [StructMerge] for all of their types.
+/// The target can be owned or a mutable reference. It's evaluated for each struct, so it
+/// should be a variable or field.
+///
+/// For example:
+/// ```ignore
+/// merge_chain!(config, file_patch, env_patch, cli_args);
+/// ```
+#[macro_export]
+macro_rules! merge_chain {
+    ($target:expr, $($src:expr),+ $(,)?) => {{
+        use $crate::StructMerge as _;
+        // Method calls auto-reference owned targets and dereference borrowed ones.
+        $($target.merge($src);)+
+    }};
+}
+
+/// Fold an iterator of structs into a target via [StructMerge].
+pub trait FoldMerge: Iterator + Sized {
+    /// Merge all items into the given target in order and return it.
+    /// Later items take precedence over earlier ones.
+    ///
+    /// For example:
+    /// ```ignore
+    /// let config = patches.into_iter().fold_merge(Config::default());
+    /// ```
+    fn fold_merge<Target>(self, target: Target) -> Target
+    where
+        Target: StructMerge<Self::Item>,
+    {
+        self.fold(target, StructMerge::into_merged)
+    }
 }
 
+impl<I: Iterator> FoldMerge for I {}
+
 /// Merge another borrowed struct into `Self`.
 ///
-/// All fields to be merged on the borrowed struct have to implement [Clone].
+/// All fields to be merged on the borrowed struct have to implement [Clone], unless they're
+/// copied or converted via `#[merge(copy)]`, `#[merge(to_owned)]` or `#[merge(ref_with = "fn")]`.
 pub trait StructMergeRef<Src> {
     /// Merge the given struct into `Self`.
     fn merge_ref(&mut self, src: &Src);
@@ -71,6 +202,51 @@ pub trait StructMergeRef<Src> {
     /// assert_eq!(target.a, "test".to_string());
     /// ```
     fn merge_ref_soft(&mut self, src: &Src);
+
+    /// Same as [StructMergeRef::merge_ref], but a [MergeReport] of all touched fields is returned.
+    fn merge_ref_report(&mut self, src: &Src) -> MergeReport;
+
+    /// Same as [StructMergeRef::merge_ref_soft], but a [MergeReport] of all touched fields is
+    /// returned.
+    fn merge_ref_soft_report(&mut self, src: &Src) -> MergeReport;
+
+    /// Merge the given struct into a clone of `Self` and return it.
+    /// `Self` is left untouched.
+    ///
+    /// For example:
+    /// ```ignore
+    /// let config = defaults.merged_ref(&patch);
+    /// ```
+    fn merged_ref(&self, src: &Src) -> Self
+    where
+        Self: Clone,
+    {
+        let mut target = self.clone();
+        target.merge_ref(src);
+        target
+    }
+
+    /// Same as [StructMergeRef::merged_ref], but the given struct is merged via
+    /// [StructMergeRef::merge_ref_soft].
+    fn merged_ref_soft(&self, src: &Src) -> Self
+    where
+        Self: Clone,
+    {
+        let mut target = self.clone();
+        target.merge_ref_soft(src);
+        target
+    }
+
+    /// Merge the given struct into `Self` whilst consuming it and return the result.
+    ///
+    /// Contrary to [StructMergeRef::merged_ref], `Self` doesn't need to implement [Clone].
+    fn into_merged_ref(mut self, src: &Src) -> Self
+    where
+        Self: Sized,
+    {
+        self.merge_ref(src);
+        self
+    }
 }
 
 /// Counterpart of [StructMergeRef].
@@ -81,6 +257,12 @@ pub trait StructMergeIntoRef<Target: ?Sized> {
 
     /// Check the [StructMergeRef::merge_ref_soft] docs.
     fn merge_into_ref_soft(&self, target: &mut Target);
+
+    /// Check the [StructMergeRef::merge_ref_report] docs.
+    fn merge_into_ref_report(&self, target: &mut Target) -> MergeReport;
+
+    /// Check the [StructMergeRef::merge_ref_soft_report] docs.
+    fn merge_into_ref_soft_report(&self, target: &mut Target) -> MergeReport;
 }
 
 /// Implement the [StructMergeRef] trait for all types that provide [StructMergeInto] for it.
@@ -92,8 +274,634 @@ impl<Target, Src: StructMergeIntoRef<Target>> StructMergeRef<Src> for Target {
     fn merge_ref_soft(&mut self, src: &Src) {
         src.merge_into_ref_soft(self);
     }
+
+    fn merge_ref_report(&mut self, src: &Src) -> MergeReport {
+        src.merge_into_ref_report(self)
+    }
+
+    fn merge_ref_soft_report(&mut self, src: &Src) -> MergeReport {
+        src.merge_into_ref_soft_report(self)
+    }
+}
+
+/// Merge another mutably borrowed struct into `Self` by taking its values.
+///
+/// Merged values are moved out of the src via [std::mem::take] and [Option::take], so they're
+/// neither cloned nor is the src consumed. The src is left with default values, e.g. for reuse
+/// as a buffer. Fields that aren't merged, e.g. by soft merges, are left untouched.
+///
+/// All fields to be merged, which aren't optional, have to implement [Default].
+/// Nested fields and arrays with optional elements aren't supported.
+pub trait StructMergeTake<Src> {
+    /// Merge the given struct into `Self` whilst taking its values.
+    fn merge_take(&mut self, src: &mut Src);
+
+    /// Merge the given struct into `Self` whilst taking its values.
+    ///
+    /// Nearly the same as `merge_take`, but any `Self::Option<T>` fields will only get merged if
+    /// the value of the field is `None`. Values that aren't merged are left in the src.
+    fn merge_take_soft(&mut self, src: &mut Src);
+
+    /// Same as [StructMergeTake::merge_take], but a [MergeReport] of all touched fields is
+    /// returned.
+    fn merge_take_report(&mut self, src: &mut Src) -> MergeReport;
+
+    /// Same as [StructMergeTake::merge_take_soft], but a [MergeReport] of all touched fields is
+    /// returned.
+    fn merge_take_soft_report(&mut self, src: &mut Src) -> MergeReport;
+}
+
+/// Counterpart of [StructMergeTake].
+/// This will merge `&mut Self` into a given target by taking its values.
+pub trait StructMergeIntoTake<Target: ?Sized> {
+    /// Check the [StructMergeTake::merge_take] docs.
+    fn merge_into_take(&mut self, target: &mut Target);
+
+    /// Check the [StructMergeTake::merge_take_soft] docs.
+    fn merge_into_take_soft(&mut self, target: &mut Target);
+
+    /// Check the [StructMergeTake::merge_take_report] docs.
+    fn merge_into_take_report(&mut self, target: &mut Target) -> MergeReport;
+
+    /// Check the [StructMergeTake::merge_take_soft_report] docs.
+    fn merge_into_take_soft_report(&mut self, target: &mut Target) -> MergeReport;
+}
+
+/// Implement the [StructMergeTake] trait for all types that provide [StructMergeIntoTake] for it.
+impl<Target, Src: StructMergeIntoTake<Target>> StructMergeTake<Src> for Target {
+    fn merge_take(&mut self, src: &mut Src) {
+        src.merge_into_take(self);
+    }
+
+    fn merge_take_soft(&mut self, src: &mut Src) {
+        src.merge_into_take_soft(self);
+    }
+
+    fn merge_take_report(&mut self, src: &mut Src) -> MergeReport {
+        src.merge_into_take_report(self)
+    }
+
+    fn merge_take_soft_report(&mut self, src: &mut Src) -> MergeReport {
+        src.merge_into_take_soft_report(self)
+    }
+}
+
+/// Fallibly merge another struct into `Self`.
+///
+/// Values of fields with different types are converted via [TryFrom].
+/// Fields and the merged result can be validated.
+/// Check the `try_struct_merge` macro docs for all options.
+pub trait TryStructMerge<Src> {
+    /// The error that's returned, if a conversion or validation fails.
+    type Error;
+
+    /// Try to merge the given struct into `Self` whilst consuming it.
+    ///
+    /// If any conversion or validation fails, `Self` is left untouched.
+    ///
+    /// For example:
+    /// ```ignore
+    /// struct Target { port: u16 };
+    /// struct Src { port: u32 };
+    ///
+    /// let mut target = Target { port: 80 };
+    /// let src = Src { port: 100_000 };
+    ///
+    /// // The port doesn't fit into a `u16`.
+    /// assert!(target.try_merge(src).is_err());
+    /// assert_eq!(target.port, 80);
+    /// ```
+    fn try_merge(&mut self, src: Src) -> Result<(), Self::Error>;
+}
+
+/// Counterpart of [TryStructMerge].
+/// This will fallibly merge `Self` into a given target.
+pub trait TryStructMergeInto<Target: ?Sized> {
+    /// Check the [TryStructMerge::Error] docs.
+    type Error;
+
+    /// Check the [TryStructMerge::try_merge] docs.
+    fn try_merge_into(self, target: &mut Target) -> Result<(), Self::Error>;
+}
+
+/// Implement the [TryStructMerge] trait for all types that provide [TryStructMergeInto] for it.
+impl<Target, Src: TryStructMergeInto<Target>> TryStructMerge<Src> for Target {
+    type Error = Src::Error;
+
+    fn try_merge(&mut self, src: Src) -> Result<(), Self::Error> {
+        src.try_merge_into(self)
+    }
+}
+
+/// The default error of [TryStructMerge].
+///
+/// Custom error types must implement `From<MergeError>`.
+#[derive(Debug)]
+pub struct MergeError {
+    field: Option<&'static str>,
+    source: Box<dyn Error + Send + Sync>,
+}
+
+impl MergeError {
+    /// Create a new error for the given target field.
+    /// If `field` is `None`, the validation of the whole target failed.
+    pub fn new(
+        field: Option<&'static str>,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        MergeError {
+            field,
+            source: source.into(),
+        }
+    }
+
+    /// The name of the target field that failed to be merged.
+    /// `None`, if the validation of the whole target failed.
+    pub fn field(&self) -> Option<&'static str> {
+        self.field
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field {
+            Some(field) => write!(f, "Failed to merge field '{}': {}", field, self.source),
+            None => write!(f, "Failed to validate merged struct: {}", self.source),
+        }
+    }
+}
+
+impl Error for MergeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// A summary of the fields that have been touched during a merge.
+///
+/// All fields are identified by their name on the target struct.
+/// Target fields without a respective src field are never part of a report.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Fields that have been written to.
+    pub written: Vec<&'static str>,
+    /// Fields that have been skipped.
+    /// E.g. because the src value was `None` or the target value was already `Some` during a
+    /// soft merge.
+    pub skipped: Vec<&'static str>,
+    /// Fields, whose values actually changed.
+    ///
+    /// These are only recorded if the src struct is annotated with `#[merge(track_changes)]`.
+    /// All merged fields must then implement [PartialEq].
+    pub changed: Vec<&'static str>,
+    /// The variant, which replaced the target's variant during an enum merge.
+    ///
+    /// All fields of the new variant are then recorded as written and, if changes are tracked,
+    /// as changed. If the replacement is skipped, they're recorded as skipped.
+    pub variant: Option<&'static str>,
+}
+
+impl MergeReport {
+    /// Whether any field has been written to or the target's variant has been replaced.
+    pub fn is_written(&self) -> bool {
+        !self.written.is_empty() || self.variant.is_some()
+    }
+
+    /// Whether any field actually changed its value or the target's variant has been replaced.
+    ///
+    /// Unless the variant has been replaced, this always returns `false`, if changes aren't
+    /// tracked.
+    pub fn is_changed(&self) -> bool {
+        !self.changed.is_empty() || self.variant.is_some()
+    }
+}
+
+/// The labels of the sources, which last set the fields of a target.
+///
+/// All fields are identified by their name on the target struct, just like in a [MergeReport].
+/// Fields that haven't been set by any source yet don't have a label.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provenance {
+    labels: BTreeMap<&'static str, String>,
+}
+
+impl Provenance {
+    /// Get the label of the source, which last set the given field.
+    ///
+    /// For example:
+    /// ```ignore
+    /// assert_eq!(config.provenance().field("timeout"), Some("env"));
+    /// ```
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.labels.get(name).map(String::as_str)
+    }
+
+    /// Iterate over all fields that have been set and the labels of their sources.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.labels
+            .iter()
+            .map(|(name, label)| (*name, label.as_str()))
+    }
+
+    /// Label all written fields of the given report with the label of their source.
+    pub fn record(&mut self, report: &MergeReport, label: &str) {
+        for name in report.written.iter() {
+            self.labels.insert(name, label.to_string());
+        }
+    }
+}
+
+/// A target, which remembers the source that last set each of its fields.
+///
+/// Sources are merged via [Tracked::merge_tracked] and [Tracked::merge_ref_tracked], which
+/// label all written fields in the target's [Provenance].
+/// The target can be accessed via [Deref], but it can't be mutated, so the provenance stays
+/// accurate.
+///
+/// For example:
+/// ```ignore
+/// let mut config = Tracked::new(Config::default());
+/// config.merge_tracked(file, "file");
+/// config.merge_tracked(env, "env");
+/// assert_eq!(config.provenance().field("timeout"), Some("env"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tracked<T> {
+    value: T,
+    provenance: Provenance,
+}
+
+impl<T> Tracked<T> {
+    /// Track the given target, whose fields haven't been set by any source yet.
+    pub fn new(value: T) -> Self {
+        Tracked {
+            value,
+            provenance: Provenance::default(),
+        }
+    }
+
+    /// Merge the given struct into the target whilst consuming it and label all written fields.
+    pub fn merge_tracked<Src>(&mut self, src: Src, label: &str) -> MergeReport
+    where
+        T: StructMerge<Src>,
+    {
+        let report = self.value.merge_report(src);
+        self.provenance.record(&report, label);
+        report
+    }
+
+    /// Merge the given borrowed struct into the target and label all written fields.
+    pub fn merge_ref_tracked<Src>(&mut self, src: &Src, label: &str) -> MergeReport
+    where
+        T: StructMergeRef<Src>,
+    {
+        let report = self.value.merge_ref_report(src);
+        self.provenance.record(&report, label);
+        report
+    }
+
+    /// The labels of the sources, which last set the fields of the target.
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+
+    /// Get the target and its provenance.
+    pub fn into_parts(self) -> (T, Provenance) {
+        (self.value, self.provenance)
+    }
+
+    /// Get the target without its provenance.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Preview the changes of merging another borrowed struct into `Self`, without touching `Self`.
+///
+/// The implementation is only generated, if a src struct with a `struct_merge_ref` attribute is
+/// annotated with `#[merge(preview)]`. All fields to be merged on the borrowed struct then have to
+/// implement [PartialEq] and [Debug](std::fmt::Debug).
+///
+/// The old and new values of a [FieldDiff] are rendered via their
+/// [Debug](std::fmt::Debug) representation, as fields of different types are collected in a
+/// single [MergeDiff]. They're meant to be displayed, e.g. for a confirmation prompt.
+/// To inspect the typed values, merge into a clone of `Self` via
+/// [StructMergeRef::merged_ref] instead.
+pub trait StructMergePreview<Src> {
+    /// Compute the changes that [StructMergeRef::merge_ref] would apply to `Self`.
+    ///
+    /// Only fields whose values would actually change are part of the [MergeDiff].
+    fn merge_preview(&self, src: &Src) -> MergeDiff;
+}
+
+/// Counterpart of [StructMergePreview].
+/// This will compute the changes of merging `&Self` into a given target.
+pub trait StructMergeIntoPreview<Target: ?Sized> {
+    /// Check the [StructMergePreview::merge_preview] docs.
+    fn merge_into_preview(&self, target: &Target) -> MergeDiff;
+}
+
+/// Implement the [StructMergePreview] trait for all types that provide [StructMergeIntoPreview]
+/// for it.
+impl<Target, Src: StructMergeIntoPreview<Target>> StructMergePreview<Src> for Target {
+    fn merge_preview(&self, src: &Src) -> MergeDiff {
+        src.merge_into_preview(self)
+    }
+}
+
+/// The change of a single field, as it would be applied by a merge.
+///
+/// Both values are rendered via their [Debug](std::fmt::Debug) representation, so they cannot
+/// be inspected as typed values. Check the [StructMergePreview] docs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The name of the target field.
+    pub name: &'static str,
+    /// The current value of the target field.
+    pub old: String,
+    /// The value of the target field after merging.
+    pub new: String,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.name, self.old, self.new)
+    }
+}
+
+/// All changes that would be applied by a merge.
+/// Check the [StructMergePreview::merge_preview] docs.
+///
+/// The [Display](std::fmt::Display) implementation renders one aligned line per changed field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeDiff {
+    pub fields: Vec<FieldDiff>,
+}
+
+impl MergeDiff {
+    /// Whether the merge wouldn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Get the change of a specific target field, if it would change.
+    pub fn field(&self, name: &str) -> Option<&FieldDiff> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+impl fmt::Display for MergeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+
+        // Align all values by padding the field names to the same width.
+        let width = self
+            .fields
+            .iter()
+            .map(|field| field.name.len())
+            .max()
+            .unwrap_or_default();
+        for field in &self.fields {
+            writeln!(
+                f,
+                "{:width$}: {} -> {}",
+                field.name,
+                field.old,
+                field.new,
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Build a src struct from the values of `Self`, i.e. the reverse of merging it into `Self`.
+///
+/// All fields of the src struct need a respective field on `Self`, whose value is cloned.
+/// The implementation is only generated, if the src struct is annotated with
+/// `#[merge(extract)]`.
+pub trait StructExtract<Src> {
+    /// Build the src struct from the current values of `Self`.
+    ///
+    /// Optional src fields are wrapped into `Some`, so merging the result into `Self` again
+    /// doesn't change anything.
+    ///
+    /// For example:
+    /// ```ignore
+    /// struct Target { a: String };
+    /// struct Src { a: Option<String> };
+    ///
+    /// let target = Target { a: "test".to_string() };
+    ///
+    /// let src: Src = target.extract();
+    /// assert_eq!(src.a, Some("test".to_string()));
+    /// ```
+    fn extract(&self) -> Src;
+}
+
+/// Counterpart of [StructExtract].
+/// This will build `Self` from a given target.
+pub trait StructExtractFrom<Target: ?Sized> {
+    /// Check the [StructExtract::extract] docs.
+    fn extract_from(target: &Target) -> Self;
+}
+
+/// Implement the [StructExtract] trait for all types that provide [StructExtractFrom] for it.
+impl<Target, Src: StructExtractFrom<Target>> StructExtract<Src> for Target {
+    fn extract(&self) -> Src {
+        Src::extract_from(self)
+    }
+}
+
+/// Compute the changes between two targets as a src struct, e.g. to store and replay them.
+///
+/// All target fields have to implement [PartialEq] and [Clone].
+/// The implementation is only generated, if the src struct is annotated with `#[merge(diff)]`.
+/// Nested fields with optional targets are rejected, as their merges never create a value.
+pub trait StructDiff<Target: ?Sized> {
+    /// Build a src struct from the fields that changed from `old` to `new`.
+    /// Merging it into `old` results in `new`.
+    ///
+    /// Optional src fields are only set, if their target field changed.
+    /// Required src fields are always set to the new value.
+    /// Nested merges never create values in empty targets, so such changes are left out.
+    ///
+    /// For example:
+    /// ```ignore
+    /// struct Target { a: String, b: String };
+    /// struct Src { a: Option<String>, b: Option<String> };
+    ///
+    /// let old = Target { a: "old".to_string(), b: "old".to_string() };
+    /// let new = Target { a: "new".to_string(), b: "old".to_string() };
+    ///
+    /// let src = Src::diff(&old, &new);
+    /// assert_eq!(src.a, Some("new".to_string()));
+    /// assert_eq!(src.b, None);
+    /// ```
+    fn diff(old: &Target, new: &Target) -> Self;
+}
+
+/// Three-way merge another borrowed struct into `Self`, using a common base.
+///
+/// All fields to be merged on the borrowed struct have to implement [Clone] and [PartialEq].
+pub trait StructMerge3<Src> {
+    /// Merge the changes of `theirs` into `Self`, using `base` as their common ancestor.
+    ///
+    /// A field is only merged, if `theirs` changed it in respect to `base` while `Self` didn't.
+    /// If both sides changed a field to different values, the field is left untouched.
+    /// The names of all such conflicting fields are returned.
+    ///
+    /// For example:
+    /// ```ignore
+    /// struct Target { a: String, b: String };
+    /// struct Src { a: String, b: String };
+    ///
+    /// let base = Src { a: "base".to_string(), b: "base".to_string() };
+    /// let theirs = Src { a: "theirs".to_string(), b: "theirs".to_string() };
+    /// let mut target = Target { a: "base".to_string(), b: "ours".to_string() };
+    ///
+    /// let conflicts = target.merge3(&base, &theirs);
+    /// // `a` only changed in `theirs`, while `b` changed on both sides.
+    /// assert_eq!(target.a, "theirs".to_string());
+    /// assert_eq!(target.b, "ours".to_string());
+    /// assert_eq!(conflicts, vec!["b"]);
+    /// ```
+    fn merge3(&mut self, base: &Src, theirs: &Src) -> Vec<&'static str>;
+}
+
+/// Counterpart of [StructMerge3].
+/// This will three-way merge `&Self` into a given target.
+pub trait StructMerge3Into<Target: ?Sized> {
+    /// Check the [StructMerge3::merge3] docs.
+    /// `self` are the changes (theirs), which will be merged into the target (ours).
+    fn merge3_into(&self, base: &Self, target: &mut Target) -> Vec<&'static str>;
+}
+
+/// Implement the [StructMerge3] trait for all types that provide [StructMerge3Into] for it.
+impl<Target, Src: StructMerge3Into<Target>> StructMerge3<Src> for Target {
+    fn merge3(&mut self, base: &Src, theirs: &Src) -> Vec<&'static str> {
+        theirs.merge3_into(base, self)
+    }
+}
+
+/// A tri-state value for partial updates, such as the bodies of REST `PATCH` requests.
+///
+/// Merging a `Patch<T>` field into an `Option<T>` field
+/// - leaves the target untouched, if the patch is [Patch::Unset],
+/// - sets the target to `None`, if the patch is [Patch::Clear],
+/// - sets the target to `Some(value)`, if the patch is [Patch::Set].
+///
+/// This is the same as merging an `Option<Option<T>>` field, but more explicit.
+///
+/// With the `serde` feature, `null` is deserialized as [Patch::Clear] and values as
+/// [Patch::Set]. Missing fields need `#[serde(default)]` to become [Patch::Unset].
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct UserPatch {
+///     #[serde(default, skip_serializing_if = "Patch::is_unset")]
+///     nickname: Patch<String>,
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Patch<T> {
+    /// The target is left untouched.
+    #[default]
+    Unset,
+    /// The target is cleared.
+    Clear,
+    /// The target is set to this value.
+    Set(T),
+}
+
+impl<T> Patch<T> {
+    /// Whether this patch leaves the target untouched.
+    pub fn is_unset(&self) -> bool {
+        matches!(self, Patch::Unset)
+    }
+
+    /// Convert from `&Patch<T>` to `Patch<&T>`.
+    pub fn as_ref(&self) -> Patch<&T> {
+        match self {
+            Patch::Unset => Patch::Unset,
+            Patch::Clear => Patch::Clear,
+            Patch::Set(value) => Patch::Set(value),
+        }
+    }
+
+    /// Convert this patch into its `Option<Option<T>>` representation.
+    /// `Unset` is `None`, `Clear` is `Some(None)` and `Set(value)` is `Some(Some(value))`.
+    pub fn into_option(self) -> Option<Option<T>> {
+        match self {
+            Patch::Unset => None,
+            Patch::Clear => Some(None),
+            Patch::Set(value) => Some(Some(value)),
+        }
+    }
+
+    /// Convert from `&Patch<T>` to `Option<Option<&T>>`.
+    /// Check the [Patch::into_option] docs.
+    pub fn as_option(&self) -> Option<Option<&T>> {
+        self.as_ref().into_option()
+    }
+}
+
+impl<T> From<Option<Option<T>>> for Patch<T> {
+    fn from(value: Option<Option<T>>) -> Self {
+        match value {
+            None => Patch::Unset,
+            Some(None) => Patch::Clear,
+            Some(Some(value)) => Patch::Set(value),
+        }
+    }
+}
+
+impl<T> From<Patch<T>> for Option<Option<T>> {
+    fn from(patch: Patch<T>) -> Self {
+        patch.into_option()
+    }
+}
+
+/// [Patch::Set] is serialized as its value, [Patch::Clear] as `null`.
+/// [Patch::Unset] is serialized as `null` as well, so it should be skipped via
+/// `#[serde(skip_serializing_if = "Patch::is_unset")]`.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Patch<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Patch::Set(value) => serializer.serialize_some(value),
+            Patch::Unset | Patch::Clear => serializer.serialize_none(),
+        }
+    }
+}
+
+/// `null` is deserialized as [Patch::Clear], any other value as [Patch::Set].
+/// Missing fields are only deserialized as [Patch::Unset], if they're annotated with
+/// `#[serde(default)]`.
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Patch<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Option::<T>::deserialize(deserializer)?;
+        Ok(match value {
+            Some(value) => Patch::Set(value),
+            None => Patch::Clear,
+        })
+    }
 }
 
 pub mod prelude {
-    pub use super::{StructMerge, StructMergeRef};
+    pub use super::{
+        FoldMerge, Patch, StructDiff, StructExtract, StructMerge, StructMerge3, StructMergePreview,
+        StructMergeRef, StructMergeTake, Tracked, TryStructMerge,
+    };
 }